@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX deliveries_idx_status_next_attempt_at;
+DROP TABLE "deliveries";
@@ -0,0 +1,17 @@
+-- Your SQL goes here
+CREATE TABLE "deliveries" (
+    "id" BIGSERIAL PRIMARY KEY,
+    "actor_id" bigint NOT NULL,
+    "inbox_uri" varchar NOT NULL,
+    "body" text NOT NULL,
+    "status" varchar NOT NULL DEFAULT 'pending',
+    "attempts" integer NOT NULL DEFAULT 0,
+    "last_status_code" integer,
+    "last_error" text,
+    "next_attempt_at" timestamp NOT NULL,
+    "created_at" timestamp NOT NULL,
+    "updated_at" timestamp,
+    CONSTRAINT "fk_deliveries_actor" FOREIGN KEY ("actor_id") REFERENCES "actors"("id") ON DELETE CASCADE
+);
+
+CREATE INDEX deliveries_idx_status_next_attempt_at ON deliveries (status, next_attempt_at);
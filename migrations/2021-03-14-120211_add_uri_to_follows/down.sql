@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX follows_unique_idx_uri;
+ALTER TABLE "follows" DROP COLUMN "uri";
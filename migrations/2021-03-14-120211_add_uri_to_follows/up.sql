@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE "follows" ADD COLUMN "uri" VARCHAR;
+CREATE UNIQUE INDEX follows_unique_idx_uri ON follows (uri);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX activities_idx_actor_id_published;
+DROP INDEX activities_unique_idx_uri;
+DROP TABLE "activities";
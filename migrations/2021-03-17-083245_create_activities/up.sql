@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE "activities" (
+    "id" BIGSERIAL PRIMARY KEY,
+    "uri" VARCHAR NOT NULL,
+    "actor_id" bigint NOT NULL,
+    "kind" VARCHAR NOT NULL,
+    "body" TEXT NOT NULL,
+    "is_public" BOOLEAN NOT NULL DEFAULT FALSE,
+    "published" TIMESTAMP NOT NULL,
+    CONSTRAINT "fk_activities_actor" FOREIGN KEY ("actor_id") REFERENCES "actors"("id") ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX activities_unique_idx_uri ON activities (uri);
+CREATE INDEX activities_idx_actor_id_published ON activities (actor_id, published);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX posts_idx_thread_id;
+DROP INDEX posts_idx_community_id_published;
+DROP INDEX posts_unique_idx_uri;
+DROP TABLE "posts";
@@ -0,0 +1,23 @@
+-- Your SQL goes here
+CREATE TABLE "posts" (
+    "id" BIGSERIAL PRIMARY KEY,
+    "uri" VARCHAR NOT NULL,
+    "url" VARCHAR,
+    "kind" VARCHAR NOT NULL DEFAULT 'Note',
+    "author_id" bigint NOT NULL,
+    "community_id" bigint NOT NULL,
+    "in_reply_to_id" bigint,
+    "thread_id" bigint,
+    "title" VARCHAR,
+    "content" TEXT NOT NULL DEFAULT '',
+    "published" TIMESTAMP NOT NULL,
+    "updated_at" TIMESTAMP,
+    CONSTRAINT "fk_posts_author" FOREIGN KEY ("author_id") REFERENCES "actors"("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_posts_community" FOREIGN KEY ("community_id") REFERENCES "actors"("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_posts_in_reply_to" FOREIGN KEY ("in_reply_to_id") REFERENCES "posts"("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_posts_thread" FOREIGN KEY ("thread_id") REFERENCES "posts"("id") ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX posts_unique_idx_uri ON posts (uri);
+CREATE INDEX posts_idx_community_id_published ON posts (community_id, published);
+CREATE INDEX posts_idx_thread_id ON posts (thread_id);
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE "communities" (
+    "actor_id" bigint PRIMARY KEY,
+    "owner_id" bigint NOT NULL,
+    "private_key_pem" TEXT NOT NULL,
+    "created_at" TIMESTAMP NOT NULL,
+    CONSTRAINT "fk_communities_actor" FOREIGN KEY ("actor_id") REFERENCES "actors"("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_communities_owner" FOREIGN KEY ("owner_id") REFERENCES "actors"("id")
+);
+
+CREATE INDEX communities_idx_owner_id ON communities (owner_id);
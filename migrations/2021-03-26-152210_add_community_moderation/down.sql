@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE "community_bans";
+
+ALTER TABLE "posts" DROP COLUMN "is_pinned";
+ALTER TABLE "posts" DROP COLUMN "is_removed";
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+ALTER TABLE "posts" ADD COLUMN "is_removed" BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE "posts" ADD COLUMN "is_pinned" BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE "community_bans" (
+    "community_id" bigint,
+    "actor_id" bigint,
+    "moderator_id" bigint NOT NULL,
+    "reason" TEXT NOT NULL DEFAULT '',
+    "created_at" TIMESTAMP NOT NULL,
+    "expires_at" TIMESTAMP,
+    PRIMARY KEY ("community_id", "actor_id"),
+    CONSTRAINT "fk_community_bans_community" FOREIGN KEY ("community_id") REFERENCES "actors"("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_community_bans_actor" FOREIGN KEY ("actor_id") REFERENCES "actors"("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_community_bans_moderator" FOREIGN KEY ("moderator_id") REFERENCES "actors"("id") ON DELETE CASCADE
+);
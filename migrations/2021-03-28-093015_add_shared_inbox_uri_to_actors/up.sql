@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE "actors" ADD COLUMN "shared_inbox_uri" VARCHAR;
+
+UPDATE "actors" SET "shared_inbox_uri" = 'https://' || "domain" || '/inbox'
+WHERE "id" IN (SELECT "actor_id" FROM "users" UNION SELECT "actor_id" FROM "communities");
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+CREATE TABLE "processed_activities" (
+    "uri" VARCHAR PRIMARY KEY,
+    "processed_at" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX "processed_activities_processed_at" ON "processed_activities" ("processed_at");
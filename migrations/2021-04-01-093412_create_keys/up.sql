@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE "keys" (
+    "id" BIGSERIAL PRIMARY KEY,
+    "uri" VARCHAR NOT NULL UNIQUE,
+    "actor_id" BIGINT NOT NULL,
+    "public_key_pem" TEXT NOT NULL,
+    "created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+    CONSTRAINT "fk_keys_actor_id" FOREIGN KEY ("actor_id") REFERENCES "actors" ("id") ON DELETE CASCADE
+);
+CREATE INDEX "keys_actor_id" ON "keys" ("actor_id");
+
+-- Until now, every key was the main key of its actor.
+INSERT INTO "keys" ("uri", "actor_id", "public_key_pem")
+    SELECT "uri" || '#main-key', "id", "public_key_pem" FROM "actors";
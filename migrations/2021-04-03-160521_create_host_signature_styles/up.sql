@@ -0,0 +1,6 @@
+-- Your SQL goes here
+CREATE TABLE "host_signature_styles" (
+    "host" VARCHAR PRIMARY KEY,
+    "style" VARCHAR NOT NULL,
+    "updated_at" TIMESTAMP NOT NULL DEFAULT NOW()
+);
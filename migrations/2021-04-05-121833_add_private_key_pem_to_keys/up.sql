@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE "keys" ADD COLUMN "private_key_pem" TEXT;
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE "post_revisions" (
+    "id" BIGSERIAL PRIMARY KEY,
+    "post_id" BIGINT NOT NULL,
+    "title" VARCHAR,
+    "content" TEXT NOT NULL DEFAULT '',
+    "url" VARCHAR,
+    "published" TIMESTAMP NOT NULL,
+    "created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+    CONSTRAINT "fk_post_revisions_post_id" FOREIGN KEY ("post_id") REFERENCES "posts" ("id") ON DELETE CASCADE
+);
+CREATE INDEX "post_revisions_post_id" ON "post_revisions" ("post_id");
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "posts" DROP COLUMN "downvotes";
+ALTER TABLE "posts" DROP COLUMN "upvotes";
+DROP TABLE "votes";
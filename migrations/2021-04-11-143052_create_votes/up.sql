@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE "votes" (
+    "post_id" BIGINT,
+    "actor_id" BIGINT,
+    "score" SMALLINT NOT NULL,
+    "uri" VARCHAR,
+    "created_at" TIMESTAMP NOT NULL,
+    PRIMARY KEY ("post_id", "actor_id"),
+    CONSTRAINT "fk_votes_post_id" FOREIGN KEY ("post_id") REFERENCES "posts" ("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_votes_actor_id" FOREIGN KEY ("actor_id") REFERENCES "actors" ("id") ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX "votes_unique_idx_uri" ON "votes" ("uri");
+CREATE INDEX "votes_actor_id" ON "votes" ("actor_id");
+
+ALTER TABLE "posts" ADD COLUMN "upvotes" INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE "posts" ADD COLUMN "downvotes" INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE "reactions" (
+    "id" BIGSERIAL PRIMARY KEY,
+    "post_id" BIGINT NOT NULL,
+    "actor_id" BIGINT NOT NULL,
+    "content" VARCHAR NOT NULL,
+    "emoji_url" VARCHAR,
+    "uri" VARCHAR,
+    "created_at" TIMESTAMP NOT NULL,
+    CONSTRAINT "fk_reactions_post_id" FOREIGN KEY ("post_id") REFERENCES "posts" ("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_reactions_actor_id" FOREIGN KEY ("actor_id") REFERENCES "actors" ("id") ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX "reactions_unique_idx_post_id_actor_id_content" ON "reactions" ("post_id", "actor_id", "content");
+CREATE UNIQUE INDEX "reactions_unique_idx_uri" ON "reactions" ("uri");
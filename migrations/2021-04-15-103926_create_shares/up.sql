@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE "shares" (
+    "post_id" BIGINT,
+    "actor_id" BIGINT,
+    "uri" VARCHAR,
+    "created_at" TIMESTAMP NOT NULL,
+    PRIMARY KEY ("post_id", "actor_id"),
+    CONSTRAINT "fk_shares_post_id" FOREIGN KEY ("post_id") REFERENCES "posts" ("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_shares_actor_id" FOREIGN KEY ("actor_id") REFERENCES "actors" ("id") ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX "shares_unique_idx_uri" ON "shares" ("uri");
+
+ALTER TABLE "posts" ADD COLUMN "shares" INTEGER NOT NULL DEFAULT 0;
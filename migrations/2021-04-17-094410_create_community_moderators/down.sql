@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+INSERT INTO "follows" ("follower_id", "following_id", "created_at", "updated_at", "role")
+    SELECT "actor_id", "community_id", "created_at", "created_at", 'moderator' FROM "community_moderators"
+    ON CONFLICT ("follower_id", "following_id") DO UPDATE SET "role" = 'moderator';
+UPDATE "follows" SET "role" = 'owner'
+    FROM "communities"
+    WHERE "follows"."follower_id" = "communities"."owner_id" AND "follows"."following_id" = "communities"."actor_id";
+
+DROP TABLE "community_moderators";
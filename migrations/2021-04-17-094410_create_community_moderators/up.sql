@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE "community_moderators" (
+    "community_id" BIGINT,
+    "actor_id" BIGINT,
+    "created_at" TIMESTAMP NOT NULL,
+    PRIMARY KEY ("community_id", "actor_id"),
+    CONSTRAINT "fk_community_moderators_community" FOREIGN KEY ("community_id") REFERENCES "actors" ("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_community_moderators_actor" FOREIGN KEY ("actor_id") REFERENCES "actors" ("id") ON DELETE CASCADE
+);
+
+-- Roles used to be kept in follows, and were lost on unfollowing; owners are in communities.
+INSERT INTO "community_moderators" ("community_id", "actor_id", "created_at")
+    SELECT "following_id", "follower_id", "created_at" FROM "follows" WHERE "role" = 'moderator';
+UPDATE "follows" SET "role" = 'follower' WHERE "role" IN ('moderator', 'owner');
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX "reactions_unique_idx_post_id_actor_id_content_emoji_url";
+DELETE FROM "reactions" "r1" USING "reactions" "r2"
+    WHERE "r1"."post_id" = "r2"."post_id" AND "r1"."actor_id" = "r2"."actor_id"
+        AND "r1"."content" = "r2"."content" AND "r1"."id" < "r2"."id";
+CREATE UNIQUE INDEX "reactions_unique_idx_post_id_actor_id_content" ON "reactions" ("post_id", "actor_id", "content");
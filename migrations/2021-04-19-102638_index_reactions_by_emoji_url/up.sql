@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- Custom emojis with the same shortcode are told apart by their image.
+DROP INDEX "reactions_unique_idx_post_id_actor_id_content";
+CREATE UNIQUE INDEX "reactions_unique_idx_post_id_actor_id_content_emoji_url"
+    ON "reactions" ("post_id", "actor_id", "content", COALESCE("emoji_url", ''));
@@ -1,6 +1,7 @@
 pub mod serializers;
 pub mod username;
 pub mod rsa;
+pub mod ed25519;
 pub mod webfinger;
 pub mod models;
 pub mod actions;
\ No newline at end of file
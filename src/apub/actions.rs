@@ -1,4 +1,13 @@
 pub mod actors;
+pub mod community;
+pub mod delivery;
+pub mod follow;
+pub mod housekeeping;
+pub mod inbox;
+pub mod keys;
+pub mod posts;
+pub mod reactions;
+pub mod votes;
 
 pub use actors::*;
 
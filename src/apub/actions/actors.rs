@@ -3,8 +3,11 @@ use crate::apub::models::Actor as ActorS;
 use crate::apub::webfinger::query_webfinger;
 use crate::db::models::NewActor;
 use crate::db::models::Actor as ActorM;
-use crate::db::actions::actor::{insert_new_actor, get_actor_by_uri};
+use crate::db::actions::actor::{insert_new_actor, get_actor_by_uri, touch_actor, update_actor};
+use crate::db::actions::key::set_actor_keys;
+use diesel::Connection;
 use crate::state::DbPool;
+use chrono::Utc;
 use std::convert::TryFrom;
 use tokio;
 use super::get_client;
@@ -12,20 +15,25 @@ use log;
 
 /// Fetch Actor information from remote server, and store it into ActorS.
 async fn fetch_actor(uri: &str) -> ActionResult<ActorS> {
-    get_client()?.get(uri)
+    let actor = get_client()?.get(uri)
         .header("Accept", "application/activity+json")
         .send()
         .await
         .map_err(|_e| ActionError::FetchError)?
         .json::<ActorS>()
         .await
-        .map_err(|_e| ActionError::FetchError)
+        .map_err(|_e| ActionError::FetchError)?;
+    // Otherwise a server could impersonate actors of other servers.
+    if actor.id != uri {
+        log::info!("fetched {} instead of {}", actor.id, uri);
+        return Err(ActionError::FetchError);
+    }
+    Ok(actor)
 }
 
-/// Fetch Actor information from remote server, and store it into ActorM, then insert into database.
-pub async fn fetch_actor_by_uri(db: &DbPool, uri: &str) -> ActionResult<ActorM> {
-    let conn = db.get().map_err(|_e| ActionError::InternalError)?;
-
+/// Fetch an actor from its server, and convert it to a NewActor marked as up to date, along with
+/// its keys.
+async fn fetch_new_actor(uri: &str) -> ActionResult<(NewActor, Vec<(String, String)>)> {
     let actor = fetch_actor(uri).await?;
 
     let webfinger_result = query_webfinger(String::from(uri)).await;
@@ -35,15 +43,87 @@ pub async fn fetch_actor_by_uri(db: &DbPool, uri: &str) -> ActionResult<ActorM>
         Err(_e) => NewActor::try_from(&actor)
     }.map_err(|_e| ActionError::InvalidForm)?;
 
-    
+    let now = Utc::now().naive_utc();
+    let new_actor = NewActor {
+        created_at: Some(now),
+        updated_at: Some(now),
+        ..new_actor
+    };
+    Ok((new_actor, actor.public_keys()))
+}
+
+/// Fetch Actor information from remote server, and store it into ActorM, then insert into database.
+pub async fn fetch_actor_by_uri(db: &DbPool, uri: &str) -> ActionResult<ActorM> {
+    let conn = db.get().map_err(|_e| ActionError::InternalError)?;
+
+    let (new_actor, public_keys) = fetch_new_actor(uri).await?;
+
     tokio::task::spawn_blocking(move || {
-        insert_new_actor(&conn, new_actor)
+        conn.transaction::<ActorM, ActionError, _>(|| {
+            let actor = insert_new_actor(&conn, new_actor)?;
+            set_actor_keys(&conn, &actor, &public_keys)?;
+            Ok(actor)
+        })
     })
     .await
     .map_err(|_e| ActionError::InternalError)?
     .map_err(|_e| ActionError::InsertError)
 }
 
+/// Fetch a known actor again, and update it in the database.
+///
+/// If it can't be fetched, it is only marked as up to date, so that it isn't retried right away.
+pub async fn refresh_actor(db: &DbPool, actor: &ActorM) -> ActionResult<ActorM> {
+    let conn = db.get().map_err(|_e| ActionError::InternalError)?;
+
+    let result = fetch_new_actor(actor.uri.as_str()).await;
+
+    let actor = actor.clone();
+    tokio::task::spawn_blocking(move || match result {
+        Ok((new_actor, public_keys)) => conn.transaction::<ActorM, ActionError, _>(|| {
+            let actor = update_actor(&conn, &actor, &new_actor)?;
+            set_actor_keys(&conn, &actor, &public_keys)?;
+            Ok(actor)
+        }),
+        Err(err) => {
+            touch_actor(&conn, &actor)?;
+            Err(err)
+        }
+    })
+    .await
+    .map_err(|_e| ActionError::InternalError)?
+}
+
+/// Store the new version of an actor carried by an Update the actor sent about itself.
+///
+/// The document is trusted as it is, since the Update is signed by the actor; its username and
+/// domain are kept if it is already known.
+pub async fn receive_actor_update(db: &DbPool, actor: &ActorS) -> ActionResult<ActorM> {
+    let conn = db.get().map_err(|_e| ActionError::InternalError)?;
+
+    let now = Utc::now().naive_utc();
+    let new_actor = NewActor {
+        created_at: Some(now),
+        updated_at: Some(now),
+        ..NewActor::try_from(actor).map_err(|_e| ActionError::InvalidForm)?
+    };
+    let public_keys = actor.public_keys();
+
+    tokio::task::spawn_blocking(move || {
+        conn.transaction::<ActorM, ActionError, _>(|| {
+            let actor = match get_actor_by_uri(&conn, new_actor.uri.as_str()) {
+                Ok(actor) => update_actor(&conn, &actor, &new_actor)?,
+                Err(ActionError::NotFound) => insert_new_actor(&conn, new_actor)?,
+                Err(err) => return Err(err),
+            };
+            set_actor_keys(&conn, &actor, &public_keys)?;
+            Ok(actor)
+        })
+    })
+    .await
+    .map_err(|_e| ActionError::InternalError)?
+}
+
 /// Get Actor from database, or fetch Actor information from remote server.
 pub async fn get_or_fetch_actor_by_uri(db: &DbPool, uri: &str) -> ActionResult<ActorM> {
     let conn = db.get().map_err(|_e| ActionError::InternalError)?;
@@ -0,0 +1,35 @@
+use crate::apub::models::{Activity, PUBLIC};
+use crate::db::actions;
+use crate::db::models::Actor;
+use crate::errors::{ActionError, ActionResult};
+use super::delivery::follower_inboxes;
+use diesel::PgConnection;
+use serde_json::json;
+use std::collections::HashSet;
+
+/// Have a community announce an activity about one of its posts, and queue the Announce for
+/// delivery to its remote followers, as FEP-1b12 describes.
+///
+/// Returns None if the community lives on another server, which relays its posts itself.
+pub fn announce_to_followers(
+    db: &PgConnection,
+    local_domains: &HashSet<String>,
+    community: &Actor,
+    activity: &Activity,
+) -> ActionResult<Option<Activity>> {
+    match actions::community::get_community_actor_by_actor_id(db, community.id) {
+        Ok(_community_actor) => (),
+        Err(ActionError::NotFound) => return Ok(None),
+        Err(err) => return Err(err),
+    }
+
+    let object = serde_json::to_value(Activity { context: None, ..activity.clone() })
+        .map_err(|_e| ActionError::InternalError)?;
+    let mut announce = Activity::new_local("Announce", community, object);
+    announce.to = Some(json!([PUBLIC]));
+    announce.cc = community.followers_uri.as_ref().map(|followers_uri| json!([followers_uri]));
+
+    let inboxes = follower_inboxes(db, local_domains, community)?;
+    actions::activity::publish_activity(db, community, &inboxes, &announce)?;
+    Ok(Some(announce))
+}
@@ -0,0 +1,398 @@
+use crate::apub::models::main_key_id;
+use crate::db::actions;
+use crate::db::models::{Actor, Delivery, Key, SIGNATURE_STYLE_CAVAGE, SIGNATURE_STYLE_RFC9421};
+use crate::errors::{ActionError, ActionResult};
+use crate::hancock;
+use crate::state::{AppState, DbPool};
+use super::get_client;
+use chrono::{Duration, Utc};
+use log;
+use openssl;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{Id, PKey, Private};
+use reqwest::StatusCode;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio;
+use warp::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use warp::http::Method;
+
+/// Sign with a key; Ed25519 keys don't take a digest, and ignore `alg`.
+pub fn do_sign(
+    key: &PKey<Private>,
+    alg: MessageDigest,
+    src: &[u8],
+) -> Result<Vec<u8>, openssl::error::ErrorStack> {
+    if key.id() == Id::ED25519 {
+        return openssl::sign::Signer::new_without_digest(key)?.sign_oneshot_to_vec(src);
+    }
+    let mut signer = openssl::sign::Signer::new(alg, key)?;
+    signer.update(src)?;
+    signer.sign_to_vec()
+}
+
+/// Value of the `Digest` header for a request body.
+pub fn digest_header_value(body: &[u8]) -> String {
+    format!("SHA-256={}", base64::encode(openssl::sha::sha256(body)))
+}
+
+/// Value of the `Content-Digest` header (RFC 9530) for a request body.
+pub fn content_digest_header_value(body: &[u8]) -> String {
+    format!("sha-256=:{}:", base64::encode(openssl::sha::sha256(body)))
+}
+
+/// Value of the `Date` header, in the IMF-fixdate format required by HTTP.
+pub fn date_header_value() -> String {
+    Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Build the `Host`, `Date`, `Digest` and `Signature` headers of a POST request to `uri`.
+pub fn sign_post_headers(
+    key_id: &str,
+    private_key_pem: &str,
+    uri: &url::Url,
+    body: &[u8],
+) -> ActionResult<HeaderMap> {
+    let host = uri.host_str().ok_or(ActionError::InvalidForm)?;
+    let host = match uri.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => String::from(host),
+    };
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{}?{}", uri.path(), query),
+        None => String::from(uri.path()),
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::HOST,
+        HeaderValue::from_str(host.as_str()).map_err(|_e| ActionError::InvalidForm)?,
+    );
+    headers.insert(
+        header::DATE,
+        HeaderValue::from_str(date_header_value().as_str()).map_err(|_e| ActionError::InternalError)?,
+    );
+    headers.insert(
+        HeaderName::from_static("digest"),
+        HeaderValue::from_str(digest_header_value(body).as_str()).map_err(|_e| ActionError::InternalError)?,
+    );
+
+    let key = PKey::private_key_from_pem(private_key_pem.as_bytes())
+        .map_err(|_e| ActionError::InternalError)?;
+    let signature = hancock::Signature::create_legacy(
+        key_id,
+        &Method::POST,
+        path_and_query.as_str(),
+        &headers,
+        |src| do_sign(&key, MessageDigest::sha256(), &src),
+    )
+    .map_err(|_e| ActionError::InternalError)?;
+
+    headers.insert(HeaderName::from_static("signature"), signature.to_header());
+    Ok(headers)
+}
+
+/// How long an RFC 9421 signature of a request is valid.
+const MESSAGE_SIGNATURE_LIFETIME_SECS: u64 = 5 * 60;
+
+/// Build the `Date`, `Content-Digest`, `Signature-Input` and `Signature` headers of a POST request
+/// to `uri`, signed as RFC 9421 requires.
+pub fn sign_post_headers_rfc9421(
+    key_id: &str,
+    private_key_pem: &str,
+    uri: &url::Url,
+    body: &[u8],
+) -> ActionResult<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::DATE,
+        HeaderValue::from_str(date_header_value().as_str()).map_err(|_e| ActionError::InternalError)?,
+    );
+    headers.insert(
+        HeaderName::from_static("content-digest"),
+        HeaderValue::from_str(content_digest_header_value(body).as_str()).map_err(|_e| ActionError::InternalError)?,
+    );
+
+    let key = PKey::private_key_from_pem(private_key_pem.as_bytes())
+        .map_err(|_e| ActionError::InternalError)?;
+    let algorithm = match key.id() {
+        Id::ED25519 => "ed25519",
+        _ => "rsa-v1_5-sha256",
+    };
+    let signature = hancock::MessageSignature::create(
+        key_id,
+        algorithm,
+        &Method::POST,
+        uri.as_str(),
+        MESSAGE_SIGNATURE_LIFETIME_SECS,
+        &headers,
+        |src| do_sign(&key, MessageDigest::sha256(), &src),
+    )
+    .map_err(|_e| ActionError::InternalError)?;
+
+    let (signature_input, signature) = signature.to_headers();
+    headers.insert(HeaderName::from_static("signature-input"), signature_input);
+    headers.insert(HeaderName::from_static("signature"), signature);
+    Ok(headers)
+}
+
+/// Sign `body` with the given key in the signature style `SIGNATURE_STYLE_RFC9421` or
+/// `SIGNATURE_STYLE_CAVAGE`, and POST it to `inbox_uri`.
+///
+/// Returns the status code of the response, whether it is successful or not.
+pub async fn post_signed_with_style(
+    style: &str,
+    key_id: &str,
+    private_key_pem: &str,
+    inbox_uri: &str,
+    body: Vec<u8>,
+) -> ActionResult<StatusCode> {
+    let uri = url::Url::parse(inbox_uri).map_err(|_e| ActionError::InvalidForm)?;
+    let headers = if style == SIGNATURE_STYLE_RFC9421 {
+        sign_post_headers_rfc9421(key_id, private_key_pem, &uri, &body)?
+    } else {
+        sign_post_headers(key_id, private_key_pem, &uri, &body)?
+    };
+
+    get_client()?
+        .post(uri)
+        .headers(headers)
+        .header(header::CONTENT_TYPE, "application/activity+json")
+        .body(body)
+        .send()
+        .await
+        .map(|response| response.status())
+        .map_err(|e| {
+            log::info!("delivery to {} failed: {}", inbox_uri, e);
+            ActionError::DeliveryError
+        })
+}
+
+/// The keys a local actor signs requests with: its main RSA key for draft-cavage signatures, and
+/// its Ed25519 key for RFC 9421 ones when it has one.
+pub struct SigningKeys {
+    pub main_key_id: String,
+    pub main_private_key_pem: String,
+    pub ed25519_key: Option<Key>,
+}
+
+impl SigningKeys {
+    /// The keyId and private key to sign with in the signature style `style`.
+    pub fn for_style(&self, style: &str) -> (&str, &str) {
+        let ed25519_key = self.ed25519_key.as_ref().and_then(|key| Some((key.uri.as_str(), key.private_key_pem.as_deref()?)));
+        match ed25519_key {
+            Some(ed25519_key) if style == SIGNATURE_STYLE_RFC9421 => ed25519_key,
+            _ => (self.main_key_id.as_str(), self.main_private_key_pem.as_str()),
+        }
+    }
+}
+
+/// How long the signature style accepted by a host is remembered.
+const SIGNATURE_STYLE_TTL_DAYS: i64 = 30;
+
+/// Like `post_signed_with_style`, signing with RFC 9421 first and then with draft-cavage if the signature is
+/// rejected, unless the host is known to accept one of them; each style uses its key of `keys`.
+///
+/// The style the host accepted is remembered for the next requests, and forgotten once the host
+/// rejects it.
+pub async fn post_signed_double_knocking(
+    db: &DbPool,
+    keys: &SigningKeys,
+    inbox_uri: &str,
+    body: Vec<u8>,
+) -> ActionResult<StatusCode> {
+    let uri = url::Url::parse(inbox_uri).map_err(|_e| ActionError::InvalidForm)?;
+    let host = match (uri.host_str(), uri.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => String::from(host),
+        (None, _) => return Err(ActionError::InvalidForm),
+    };
+
+    let conn = db.get().map_err(|_e| ActionError::InternalError)?;
+    let host_move = host.clone();
+    let since = (Utc::now() - Duration::days(SIGNATURE_STYLE_TTL_DAYS)).naive_utc();
+    let known_style = tokio::task::spawn_blocking(move || actions::delivery::get_signature_style(&conn, host_move.as_str(), since))
+        .await
+        .map_err(|_e| ActionError::InternalError)??;
+
+    let mut style = match known_style.as_deref() {
+        Some(SIGNATURE_STYLE_CAVAGE) => SIGNATURE_STYLE_CAVAGE,
+        _ => SIGNATURE_STYLE_RFC9421,
+    };
+    let (key_id, private_key_pem) = keys.for_style(style);
+    let mut status = post_signed_with_style(style, key_id, private_key_pem, inbox_uri, body.clone()).await?;
+    let rejected = status == StatusCode::BAD_REQUEST || status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN;
+    if rejected {
+        // A host may stop accepting the style it accepted before, the other one is tried again.
+        if known_style.is_some() {
+            let conn = db.get().map_err(|_e| ActionError::InternalError)?;
+            let host_move = host.clone();
+            tokio::task::spawn_blocking(move || actions::delivery::forget_signature_style(&conn, host_move.as_str()))
+                .await
+                .map_err(|_e| ActionError::InternalError)??;
+        }
+        let other_style = if style == SIGNATURE_STYLE_RFC9421 { SIGNATURE_STYLE_CAVAGE } else { SIGNATURE_STYLE_RFC9421 };
+        log::info!("{} rejected a {} signature with {}, trying {}", host, style, status, other_style);
+        style = other_style;
+        let (key_id, private_key_pem) = keys.for_style(style);
+        status = post_signed_with_style(style, key_id, private_key_pem, inbox_uri, body).await?;
+    }
+
+    if status.is_success() && known_style.as_deref() != Some(style) {
+        let conn = db.get().map_err(|_e| ActionError::InternalError)?;
+        tokio::task::spawn_blocking(move || actions::delivery::set_signature_style(&conn, host.as_str(), style))
+            .await
+            .map_err(|_e| ActionError::InternalError)??;
+    }
+    Ok(status)
+}
+
+/// Inboxes of the remote followers of an actor, each shared inbox only once.
+pub fn follower_inboxes(
+    conn: &diesel::PgConnection,
+    local_domains: &HashSet<String>,
+    actor: &Actor,
+) -> ActionResult<Vec<String>> {
+    let mut seen = HashSet::new();
+    Ok(actions::follow::actor_get_all_followers(conn, actor)?
+        .iter()
+        .filter(|follower| !local_domains.contains(&follower.domain))
+        .map(|follower| String::from(follower.delivery_inbox_uri()))
+        .filter(|inbox_uri| seen.insert(inbox_uri.clone()))
+        .collect())
+}
+
+/// How long a worker sleeps when there is no due delivery.
+const POLL_INTERVAL_SECS: u64 = 5;
+/// How long a claimed delivery is hidden from other workers while being sent.
+const DELIVERY_LEASE_SECS: i64 = 5 * 60;
+
+/// Spawn the workers sending queued deliveries in the background.
+pub fn spawn_delivery_workers(app_state: Arc<AppState>) {
+    for _ in 0..app_state.delivery_workers {
+        let app_state = Arc::clone(&app_state);
+        tokio::spawn(async move {
+            loop {
+                match process_next_delivery(&app_state).await {
+                    Ok(true) => (),
+                    Ok(false) => {
+                        tokio::time::sleep(std::time::Duration::from_secs(POLL_INTERVAL_SECS)).await
+                    }
+                    Err(e) => {
+                        log::warn!("delivery worker: {}", e);
+                        tokio::time::sleep(std::time::Duration::from_secs(POLL_INTERVAL_SECS)).await
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Send the next due delivery, if any, and record the result.
+///
+/// Returns whether a delivery was processed.
+async fn process_next_delivery(app_state: &AppState) -> ActionResult<bool> {
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+    let claimed = tokio::task::spawn_blocking(move || -> ActionResult<_> {
+        let delivery = match actions::delivery::claim_due_delivery(&conn, Duration::seconds(DELIVERY_LEASE_SECS))? {
+            Some(delivery) => delivery,
+            None => return Ok(None),
+        };
+        let sender = actions::actor::get_local_actor_with_key(&conn, delivery.actor_id).map(|(sender, private_key_pem)| {
+            // Without an Ed25519 key, RFC 9421 signatures are made with the RSA key.
+            let ed25519_key = match actions::key::get_local_ed25519_key(&conn, &sender) {
+                Ok(key) => Some(key),
+                Err(ActionError::NotFound) => None,
+                Err(e) => {
+                    log::warn!("no Ed25519 key for {}: {}", sender.uri, e);
+                    None
+                }
+            };
+            SigningKeys {
+                main_key_id: main_key_id(sender.uri.as_str()),
+                main_private_key_pem: private_key_pem,
+                ed25519_key,
+            }
+        });
+        Ok(Some((delivery, sender)))
+    })
+    .await
+    .map_err(|_e| ActionError::InternalError)??;
+
+    let (delivery, sender) = match claimed {
+        Some(claimed) => claimed,
+        None => return Ok(false),
+    };
+
+    let result = match sender {
+        Ok(keys) => post_signed_double_knocking(
+            &app_state.db,
+            &keys,
+            delivery.inbox_uri.as_str(),
+            delivery.body.clone().into_bytes(),
+        )
+        .await,
+        Err(_e) => Err(ActionError::NotFound),
+    };
+
+    let give_up_after = app_state.delivery_give_up_after;
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+    tokio::task::spawn_blocking(move || record_delivery_result(&conn, &delivery, result, give_up_after))
+        .await
+        .map_err(|_e| ActionError::InternalError)??;
+
+    Ok(true)
+}
+
+fn record_delivery_result(
+    conn: &diesel::PgConnection,
+    delivery: &Delivery,
+    result: ActionResult<StatusCode>,
+    give_up_after: Duration,
+) -> ActionResult<Delivery> {
+    match result {
+        Ok(status) if status.is_success() => {
+            actions::delivery::mark_delivered(conn, delivery, status.as_u16() as i32)
+        }
+        Ok(status) => {
+            // Client errors other than timeouts and rate limiting won't go away by retrying.
+            let permanent = status.is_client_error()
+                && status != StatusCode::REQUEST_TIMEOUT
+                && status != StatusCode::TOO_MANY_REQUESTS;
+            log::info!("delivery {} to {} rejected with {}", delivery.id, delivery.inbox_uri, status);
+            actions::delivery::mark_delivery_failed(
+                conn,
+                delivery,
+                Some(status.as_u16() as i32),
+                status.to_string().as_str(),
+                permanent,
+                give_up_after,
+            )
+        }
+        // The sender is no longer a local actor with a private key.
+        Err(ActionError::NotFound) => actions::delivery::mark_delivery_failed(
+            conn,
+            delivery,
+            None,
+            "sender has no private key",
+            true,
+            give_up_after,
+        ),
+        Err(ActionError::InvalidForm) => actions::delivery::mark_delivery_failed(
+            conn,
+            delivery,
+            None,
+            "invalid inbox uri",
+            true,
+            give_up_after,
+        ),
+        Err(err) => actions::delivery::mark_delivery_failed(
+            conn,
+            delivery,
+            None,
+            err.to_string().as_str(),
+            false,
+            give_up_after,
+        ),
+    }
+}
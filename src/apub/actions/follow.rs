@@ -0,0 +1,78 @@
+use crate::apub::models::Activity;
+use crate::db::actions;
+use crate::db::models::{Actor, Follow};
+use crate::errors::{ActionError, ActionResult};
+use diesel::{Connection, PgConnection};
+use serde_json::{json, Value};
+
+/// The Follow activity which created a follow, to be wrapped in Accept or Reject.
+fn follow_object(follower: &Actor, following: &Actor, follow: &Follow) -> Value {
+    match &follow.uri {
+        Some(uri) => json!({
+            "id": uri,
+            "type": "Follow",
+            "actor": follower.uri,
+            "object": following.uri
+        }),
+        None => json!({
+            "type": "Follow",
+            "actor": follower.uri,
+            "object": following.uri
+        }),
+    }
+}
+
+/// Answer a Follow of a local actor with `kind` (Accept or Reject), and queue it for delivery.
+fn send_follow_response(
+    db: &PgConnection,
+    kind: &str,
+    follower: &Actor,
+    following: &Actor,
+    follow: &Follow,
+) -> ActionResult<Activity> {
+    let mut activity = Activity::new_local(kind, following, follow_object(follower, following, follow));
+    activity.to = Some(json!([follower.uri]));
+    actions::activity::publish_activity(db, following, &[String::from(follower.delivery_inbox_uri())], &activity)?;
+    Ok(activity)
+}
+
+pub fn send_accept_follow(
+    db: &PgConnection,
+    follower: &Actor,
+    following: &Actor,
+    follow: &Follow,
+) -> ActionResult<Activity> {
+    send_follow_response(db, "Accept", follower, following, follow)
+}
+
+pub fn send_reject_follow(
+    db: &PgConnection,
+    follower: &Actor,
+    following: &Actor,
+    follow: &Follow,
+) -> ActionResult<Activity> {
+    send_follow_response(db, "Reject", follower, following, follow)
+}
+
+/// Follow a remote actor: record a pending follow, and queue the Follow for delivery.
+pub fn send_follow(db: &PgConnection, follower: &Actor, following: &Actor) -> ActionResult<Follow> {
+    let mut activity = Activity::new_local("Follow", follower, json!(following.uri));
+    activity.to = Some(json!([following.uri]));
+    db.transaction::<Follow, ActionError, _>(|| {
+        let follow = actions::follow::request_follow(db, follower, following, activity.id.as_str())?;
+        actions::activity::publish_activity(db, follower, &[String::from(following.delivery_inbox_uri())], &activity)?;
+        Ok(follow)
+    })
+}
+
+/// Stop following a remote actor: remove the follow, and queue an Undo of the Follow for delivery.
+pub fn send_undo_follow(db: &PgConnection, follower: &Actor, following: &Actor) -> ActionResult<Activity> {
+    db.transaction::<Activity, ActionError, _>(|| {
+        let follow = actions::follow::get_follow(db, follower, following)?;
+        let mut activity = Activity::new_local("Undo", follower, follow_object(follower, following, &follow));
+        activity.to = Some(json!([following.uri]));
+        actions::follow::unfollow_actor_by_uri(db, follower.uri.as_str(), following.uri.as_str())?;
+        actions::activity::publish_activity(db, follower, &[String::from(following.delivery_inbox_uri())], &activity)?;
+        Ok(activity)
+    })
+}
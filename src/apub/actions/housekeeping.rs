@@ -0,0 +1,50 @@
+use crate::apub;
+use crate::db::actions;
+use crate::errors::{ActionError, ActionResult};
+use crate::state::AppState;
+use chrono::Utc;
+use log;
+use std::sync::Arc;
+use tokio;
+
+/// How often the housekeeping task runs.
+const HOUSEKEEPING_INTERVAL_SECS: u64 = 60 * 60;
+/// How many stale actors are fetched again on each run.
+const ACTOR_REFRESH_BATCH: i64 = 100;
+
+/// Spawn the task periodically cleaning up the database and refreshing remote actors in the
+/// background.
+pub fn spawn_housekeeping(app_state: Arc<AppState>) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = run_housekeeping(&app_state).await {
+                log::warn!("housekeeping: {}", e);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(HOUSEKEEPING_INTERVAL_SECS)).await
+        }
+    });
+}
+
+async fn run_housekeeping(app_state: &AppState) -> ActionResult<()> {
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+    let before = (Utc::now() - app_state.processed_activity_retention).naive_utc();
+    let pruned = tokio::task::spawn_blocking(move || actions::activity::prune_processed_activities(&conn, before))
+        .await
+        .map_err(|_e| ActionError::InternalError)??;
+    log::debug!("housekeeping: forgot {} processed activities", pruned);
+
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+    let before = (Utc::now() - app_state.actor_refresh_interval).naive_utc();
+    let local_domains = app_state.local_domains.clone();
+    let stale_actors = tokio::task::spawn_blocking(move || {
+        actions::actor::get_stale_remote_actors(&conn, &local_domains, before, ACTOR_REFRESH_BATCH)
+    })
+    .await
+    .map_err(|_e| ActionError::InternalError)??;
+    for actor in stale_actors {
+        if let Err(e) = apub::actions::refresh_actor(&app_state.db, &actor).await {
+            log::info!("housekeeping: failed to refresh {}: {}", actor.uri, e);
+        }
+    }
+    Ok(())
+}
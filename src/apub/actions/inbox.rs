@@ -0,0 +1,46 @@
+use crate::apub::models::is_public_uri;
+use crate::db::actions;
+use crate::db::models::Actor;
+use crate::errors::{ActionError, ActionResult};
+use diesel::PgConnection;
+use std::collections::HashSet;
+
+/// Local actors an inbound activity is meant for, among the URIs it is addressed to.
+///
+/// These are the local actors and communities named directly, and the local followers of remote
+/// actors whose followers collection is named.
+pub fn local_recipients(
+    db: &PgConnection,
+    local_domains: &HashSet<String>,
+    addressees: &[String],
+) -> ActionResult<Vec<Actor>> {
+    let mut recipients: Vec<Actor> = vec![];
+    for uri in addressees.iter().filter(|uri| !is_public_uri(uri)) {
+        let is_local = url::Url::parse(uri)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| local_domains.contains(host)))
+            .unwrap_or(false);
+        let actors = if is_local {
+            match actions::actor::get_actor_by_uri(db, uri) {
+                Ok(actor) => vec![actor],
+                Err(ActionError::NotFound) => vec![],
+                Err(err) => return Err(err),
+            }
+        } else {
+            match actions::actor::get_actor_by_followers_uri(db, uri) {
+                Ok(actor) => actions::follow::actor_get_all_followers(db, &actor)?
+                    .into_iter()
+                    .filter(|follower| local_domains.contains(&follower.domain))
+                    .collect(),
+                Err(ActionError::NotFound) => vec![],
+                Err(err) => return Err(err),
+            }
+        };
+        for actor in actors {
+            if !recipients.iter().any(|recipient| recipient.id == actor.id) {
+                recipients.push(actor);
+            }
+        }
+    }
+    Ok(recipients)
+}
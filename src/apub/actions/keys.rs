@@ -0,0 +1,116 @@
+use crate::apub::models::is_same_origin;
+use crate::db::actions;
+use crate::db::models::{Actor, Key};
+use crate::errors::{ActionError, ActionResult};
+use crate::state::DbPool;
+use super::{fetch_actor_by_uri, get_client, refresh_actor};
+use chrono::{DateTime, Utc};
+use log;
+use serde_json::Value;
+use std::collections::HashSet;
+use tokio;
+
+/// How long after an actor was fetched a key can't cause it to be fetched again.
+const KEY_REFRESH_MIN_INTERVAL_SECS: i64 = 60;
+
+/// Whether an actor may be fetched again because of one of its keys, unknown or not verifying.
+///
+/// Local actors are never fetched, and remote ones at most once a minute, so that bad signatures
+/// don't make us hammer their servers.
+pub fn can_refresh_actor(local_domains: &HashSet<String>, actor: &Actor, now: DateTime<Utc>) -> bool {
+    if local_domains.contains(&actor.domain) {
+        return false;
+    }
+    match actor.updated_at {
+        Some(updated_at) => (now.naive_utc() - updated_at).num_seconds() >= KEY_REFRESH_MIN_INTERVAL_SECS,
+        None => true,
+    }
+}
+
+/// The actor claimed to own a key by the document its keyId resolves to: either a key with an
+/// `owner`, or an actor itself, such as for `<actor>#main-key`.
+///
+/// The claim still has to be checked against the `publicKey` of the actor.
+pub fn key_document_owner(document: &Value) -> Option<String> {
+    if let Some(owner) = document["owner"].as_str() {
+        Some(String::from(owner))
+    } else if document.get("publicKey").is_some() {
+        document["id"].as_str().map(String::from)
+    } else {
+        None
+    }
+}
+
+async fn fetch_key_document(key_id: &str) -> ActionResult<Value> {
+    get_client()?.get(key_id)
+        .header("Accept", "application/activity+json")
+        .send()
+        .await
+        .map_err(|_e| ActionError::FetchError)?
+        .json::<Value>()
+        .await
+        .map_err(|_e| ActionError::FetchError)
+}
+
+/// A known key, along with the actor owning it.
+pub async fn get_key(db: &DbPool, key_id: &str) -> ActionResult<(Actor, Key)> {
+    let conn = db.get().map_err(|_e| ActionError::InternalError)?;
+    let key_id = String::from(key_id);
+    tokio::task::spawn_blocking(move || {
+        let key = actions::key::get_key_by_uri(&conn, key_id.as_str())?;
+        let actor = actions::actor::get_actor_by_id(&conn, key.actor_id)?;
+        Ok((actor, key))
+    })
+    .await
+    .map_err(|_e| ActionError::InternalError)?
+}
+
+/// Get a key from the database, or resolve its keyId to the actor owning it.
+///
+/// The key is only trusted if the actor lists it in its `publicKey` and is served by the same
+/// host, so the actor is fetched again if already known.
+pub async fn get_or_fetch_key(db: &DbPool, local_domains: &HashSet<String>, key_id: &str) -> ActionResult<(Actor, Key)> {
+    match get_key(db, key_id).await {
+        Err(ActionError::NotFound) => (),
+        result => return result,
+    }
+
+    let key_domain = url::Url::parse(key_id)
+        .ok()
+        .and_then(|uri| uri.host_str().map(String::from))
+        .ok_or(ActionError::NotAuthenticated)?;
+    if local_domains.contains(&key_domain) {
+        return Err(ActionError::NotFound);
+    }
+
+    let document = fetch_key_document(key_id).await?;
+    let owner_uri = key_document_owner(&document).ok_or(ActionError::FetchError)?;
+    if !is_same_origin(key_id, owner_uri.as_str()) {
+        log::info!("{} can't own {}, served by another host", owner_uri, key_id);
+        return Err(ActionError::NotAuthenticated);
+    }
+
+    let conn = db.get().map_err(|_e| ActionError::InternalError)?;
+    let owner_uri_move = owner_uri.clone();
+    let owner = tokio::task::spawn_blocking(move || actions::actor::get_actor_by_uri(&conn, owner_uri_move.as_str()))
+        .await
+        .map_err(|_e| ActionError::InternalError)?;
+    match owner {
+        Ok(owner) if can_refresh_actor(local_domains, &owner, Utc::now()) => {
+            refresh_actor(db, &owner).await?;
+        }
+        Ok(_owner) => (),
+        Err(ActionError::NotFound) => {
+            fetch_actor_by_uri(db, owner_uri.as_str()).await?;
+        }
+        Err(err) => return Err(err),
+    }
+
+    get_key(db, key_id).await.map_err(|err| match err {
+        ActionError::NotFound => {
+            log::info!("{} is not a key of {}", key_id, owner_uri);
+            ActionError::NotAuthenticated
+        }
+        err => err,
+    })
+}
@@ -0,0 +1,273 @@
+use crate::apub::models::{is_same_origin, parse_datetime, uri_list, Activity as ActivityS, Object as ObjectS};
+use crate::db::actions::actor::{get_actor_by_id, get_actor_by_uri};
+use crate::db::actions::moderation::is_banned;
+use crate::db::actions::post::{get_post_by_uri, insert_comment, insert_thread, revise_post, tombstone_post};
+use crate::db::models::{Actor as ActorM, ActorType, NewPost, Post};
+use crate::errors::{ActionError, ActionResult};
+use crate::state::AppState;
+use super::community::announce_to_followers;
+use super::{get_client, get_or_fetch_actor_by_uri};
+use chrono::Utc;
+use log;
+use diesel::{Connection, PgConnection};
+use tokio;
+
+/// Fetch an object from remote server.
+pub async fn fetch_object(uri: &str) -> ActionResult<ObjectS> {
+    get_client()?.get(uri)
+        .header("Accept", "application/activity+json")
+        .send()
+        .await
+        .map_err(|_e| ActionError::FetchError)?
+        .json::<ObjectS>()
+        .await
+        .map_err(|_e| ActionError::FetchError)
+}
+
+/// Fetch an object relayed by another actor from its origin, since only its own server vouches
+/// for it.
+pub async fn fetch_object_at_origin(uri: &str) -> ActionResult<ObjectS> {
+    let object = fetch_object(uri).await?;
+    if object.id != uri {
+        log::info!("fetched {} instead of {}", object.id, uri);
+        return Err(ActionError::FetchError);
+    }
+    Ok(object)
+}
+
+/// Find the community a new thread is posted to, among the actors it is addressed to.
+fn get_addressed_community(conn: &PgConnection, object: &ObjectS) -> ActionResult<Option<ActorM>> {
+    let mut candidates = uri_list(object.audience.as_ref());
+    candidates.extend(object.recipients());
+    for uri in candidates {
+        match get_actor_by_uri(conn, uri.as_str()) {
+            Ok(actor) => {
+                if let ActorType::Group = ActorType::from(&actor) {
+                    return Ok(Some(actor));
+                }
+            }
+            Err(ActionError::NotFound) => (),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(None)
+}
+
+/// Store a Note, Page or Article by `author`, as a thread of the community it is addressed to,
+/// or as a comment to the post it replies to.
+///
+/// A new post in a local community is announced by the community to its followers, wrapping
+/// `activity`, the Create of the object; there is none to wrap when the object was only shared.
+///
+/// Returns None if the object can't be placed in any known community or thread, and fails
+/// with `Forbidden` if the author is banned from that community.
+pub async fn receive_post(
+    app_state: &AppState,
+    author: &ActorM,
+    object: &ObjectS,
+    activity: Option<&ActivityS>,
+) -> ActionResult<Option<Post>> {
+    if !object.is_post() || object.attributed_to != author.uri || !is_same_origin(&object.id, &author.uri) {
+        return Err(ActionError::InvalidForm);
+    }
+
+    // The audience names the community explicitly, it is worth fetching when still unknown.
+    if let Some(audience) = uri_list(object.audience.as_ref()).first() {
+        let _community = get_or_fetch_actor_by_uri(&app_state.db, audience.as_str()).await;
+    }
+
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+    let local_domains = app_state.local_domains.clone();
+    let author = author.clone();
+    let object = object.clone();
+    let activity = activity.cloned();
+
+    tokio::task::spawn_blocking(move || {
+        match get_post_by_uri(&conn, object.id.as_str()) {
+            Ok(post) => return Ok(Some(post)),
+            Err(ActionError::NotFound) => (),
+            Err(err) => return Err(err),
+        }
+
+        let published = object.published.as_deref().and_then(parse_datetime).unwrap_or_else(|| Utc::now().naive_utc());
+        let new_post = |community_id: i64| NewPost {
+            uri: object.id.clone(),
+            url: object.url_string(),
+            kind: object.kind.clone(),
+            author_id: author.id,
+            community_id,
+            in_reply_to_id: None,
+            thread_id: None,
+            title: object.name.clone(),
+            content: object.content.clone(),
+            published,
+            updated_at: object.updated.as_deref().and_then(parse_datetime),
+        };
+
+        let (community, parent) = match &object.in_reply_to {
+            Some(in_reply_to) => match get_post_by_uri(&conn, in_reply_to.as_str()) {
+                Ok(parent) => (get_actor_by_id(&conn, parent.community_id)?, Some(parent)),
+                Err(ActionError::NotFound) => return Ok(None),
+                Err(err) => return Err(err),
+            },
+            None => match get_addressed_community(&conn, &object)? {
+                Some(community) => (community, None),
+                None => return Ok(None),
+            },
+        };
+        if is_banned(&conn, &community, &author)? {
+            return Err(ActionError::Forbidden);
+        }
+
+        conn.transaction::<_, ActionError, _>(|| {
+            let post = match parent {
+                Some(parent) => insert_comment(&conn, &parent, new_post(community.id))?,
+                None => insert_thread(&conn, new_post(community.id))?,
+            };
+            if let Some(activity) = &activity {
+                announce_to_followers(&conn, &local_domains, &community, activity)?;
+            }
+            Ok(Some(post))
+        })
+    })
+    .await
+    .map_err(|_e| ActionError::InternalError)?
+}
+
+/// Store the edited version of a Note, Page or Article by `author`, carried by `activity`, the
+/// Update of the object.
+///
+/// Edits to unknown or deleted posts, and edits older than the stored version, are ignored and
+/// return None; it fails with `Forbidden` if `author` did not write the post.
+pub async fn receive_post_update(
+    app_state: &AppState,
+    author: &ActorM,
+    object: &ObjectS,
+    activity: &ActivityS,
+) -> ActionResult<Option<Post>> {
+    if !object.is_post() || object.attributed_to != author.uri || !is_same_origin(&object.id, &author.uri) {
+        return Err(ActionError::InvalidForm);
+    }
+
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+    let local_domains = app_state.local_domains.clone();
+    let author = author.clone();
+    let object = object.clone();
+    let activity = activity.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let post = match get_post_by_uri(&conn, object.id.as_str()) {
+            Ok(post) => post,
+            Err(ActionError::NotFound) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        if post.author_id != author.id {
+            return Err(ActionError::Forbidden);
+        }
+        if post.is_deleted() {
+            return Ok(None);
+        }
+        let updated = object.updated.as_deref().and_then(parse_datetime);
+        if let (Some(updated), Some(last_updated)) = (updated, post.updated_at) {
+            if updated <= last_updated {
+                return Ok(None);
+            }
+        }
+
+        let new_post = NewPost {
+            uri: post.uri.clone(),
+            url: object.url_string(),
+            kind: post.kind.clone(),
+            author_id: post.author_id,
+            community_id: post.community_id,
+            in_reply_to_id: post.in_reply_to_id,
+            thread_id: post.thread_id,
+            title: if post.is_thread() { object.name.clone() } else { None },
+            content: object.content.clone(),
+            published: post.published,
+            updated_at: updated,
+        };
+        let community = get_actor_by_id(&conn, post.community_id)?;
+        conn.transaction::<_, ActionError, _>(|| {
+            let post = revise_post(&conn, &post, &new_post)?;
+            announce_to_followers(&conn, &local_domains, &community, &activity)?;
+            Ok(Some(post))
+        })
+    })
+    .await
+    .map_err(|_e| ActionError::InternalError)?
+}
+
+/// Replace the post `uri` with a tombstone, as `author` asked with `activity`, a Delete.
+///
+/// Unknown and already deleted posts are ignored and return None; it fails with `Forbidden` if
+/// `author` did not write the post.
+pub async fn receive_post_delete(
+    app_state: &AppState,
+    author: &ActorM,
+    uri: &str,
+    activity: &ActivityS,
+) -> ActionResult<Option<Post>> {
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+    let local_domains = app_state.local_domains.clone();
+    let author = author.clone();
+    let uri = String::from(uri);
+    let activity = activity.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let post = match get_post_by_uri(&conn, uri.as_str()) {
+            Ok(post) => post,
+            Err(ActionError::NotFound) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        if post.author_id != author.id {
+            return Err(ActionError::Forbidden);
+        }
+        if post.is_deleted() {
+            return Ok(None);
+        }
+        let community = get_actor_by_id(&conn, post.community_id)?;
+        conn.transaction::<_, ActionError, _>(|| {
+            let post = tombstone_post(&conn, &post)?;
+            announce_to_followers(&conn, &local_domains, &community, &activity)?;
+            Ok(Some(post))
+        })
+    })
+    .await
+    .map_err(|_e| ActionError::InternalError)?
+}
+
+/// Store a post relayed by an Announce, as fetched from its origin unless it is already known, or
+/// update it if it was edited since; `activity` is the Announce, or the Create or Update it wraps.
+///
+/// Returns None if the post can't be placed in any known community or thread.
+pub async fn receive_announced_post(app_state: &AppState, uri: &str, activity: &ActivityS) -> ActionResult<Option<Post>> {
+    if activity.kind != "Update" {
+        let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+        let post_uri = String::from(uri);
+        let known = tokio::task::spawn_blocking(move || get_post_by_uri(&conn, post_uri.as_str()))
+            .await
+            .map_err(|_e| ActionError::InternalError)?;
+        match known {
+            Ok(post) => return Ok(Some(post)),
+            Err(ActionError::NotFound) => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    let object = fetch_object_at_origin(uri).await?;
+    if !object.is_post() {
+        return Err(ActionError::InvalidForm);
+    }
+    let author = get_or_fetch_actor_by_uri(&app_state.db, object.attributed_to.as_str()).await?;
+    let activity = ActivityS {
+        object: serde_json::to_value(&object).map_err(|_e| ActionError::InternalError)?,
+        ..activity.clone()
+    };
+    // Only a relayed Create can be announced in turn, not someone else's Announce.
+    let create = if activity.kind == "Create" { Some(&activity) } else { None };
+    match receive_post(app_state, &author, &object, create).await? {
+        Some(post) if activity.kind == "Update" => Ok(receive_post_update(app_state, &author, &object, &activity).await?.or(Some(post))),
+        post => Ok(post),
+    }
+}
@@ -0,0 +1,116 @@
+use crate::apub::models::Activity;
+use crate::db::actions;
+use crate::db::actions::reaction::{add_reaction, remove_reaction, remove_reaction_by_uri};
+use crate::db::models::{Actor, Reaction};
+use crate::errors::{ActionError, ActionResult};
+use crate::state::AppState;
+use super::community::announce_to_followers;
+use diesel::{Connection, PgConnection};
+use std::collections::HashSet;
+use tokio;
+
+/// Record a reaction of `reactor` to the post `post_uri`, sent with `activity`, an EmojiReact or a
+/// Like with content.
+///
+/// Reactions to unknown posts, and to deleted or removed ones, are ignored and return None.
+pub async fn receive_reaction(
+    app_state: &AppState,
+    reactor: &Actor,
+    post_uri: &str,
+    activity: &Activity,
+) -> ActionResult<Option<Reaction>> {
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+    let local_domains = app_state.local_domains.clone();
+    let reactor = reactor.clone();
+    let post_uri = String::from(post_uri);
+    let activity = activity.clone();
+
+    tokio::task::spawn_blocking(move || {
+        apply_reaction(&conn, &local_domains, &reactor, post_uri.as_str(), &activity)
+    })
+    .await
+    .map_err(|_e| ActionError::InternalError)?
+}
+
+/// The blocking part of `receive_reaction`.
+pub fn apply_reaction(
+    db: &PgConnection,
+    local_domains: &HashSet<String>,
+    reactor: &Actor,
+    post_uri: &str,
+    activity: &Activity,
+) -> ActionResult<Option<Reaction>> {
+    let content = activity.reaction_content().ok_or(ActionError::InvalidForm)?;
+    let emoji_url = activity.reaction_emoji_url();
+    let post = match actions::post::get_post_by_uri(db, post_uri) {
+        Ok(post) => post,
+        Err(ActionError::NotFound) => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    if post.is_deleted() || post.is_removed {
+        return Ok(None);
+    }
+    let community = actions::actor::get_actor_by_id(db, post.community_id)?;
+    db.transaction::<_, ActionError, _>(|| {
+        let reaction = add_reaction(
+            db,
+            reactor,
+            &post,
+            content,
+            emoji_url.as_deref(),
+            Some(activity.id.as_str()),
+        )?;
+        announce_to_followers(db, local_domains, &community, activity)?;
+        Ok(Some(reaction))
+    })
+}
+
+/// Withdraw the reaction `undone` of `reactor`, as it asked with `activity`, an Undo.
+///
+/// The reaction is found by the id of the undone activity, or else by its post and emoji. Returns
+/// None if there is no such reaction.
+pub async fn receive_undo_reaction(
+    app_state: &AppState,
+    reactor: &Actor,
+    undone: &Activity,
+    activity: &Activity,
+) -> ActionResult<Option<Reaction>> {
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+    let local_domains = app_state.local_domains.clone();
+    let reactor = reactor.clone();
+    let undone = undone.clone();
+    let activity = activity.clone();
+
+    tokio::task::spawn_blocking(move || {
+        conn.transaction::<_, ActionError, _>(|| {
+            let reaction = match remove_reaction_by_uri(&conn, &reactor, undone.id.as_str()) {
+                Ok(reaction) => reaction,
+                Err(ActionError::NotFound) => {
+                    let post_uri = match undone.object.as_str() {
+                        Some(post_uri) => post_uri,
+                        None => return Ok(None),
+                    };
+                    let content = undone.reaction_content().unwrap_or_default();
+                    let emoji_url = undone.reaction_emoji_url();
+                    let post = match actions::post::get_post_by_uri(&conn, post_uri) {
+                        Ok(post) => post,
+                        Err(ActionError::NotFound) => return Ok(None),
+                        Err(err) => return Err(err),
+                    };
+                    match remove_reaction(&conn, &reactor, &post, content, emoji_url.as_deref()) {
+                        Ok(reaction) => reaction,
+                        Err(ActionError::NotFound) => return Ok(None),
+                        Err(err) => return Err(err),
+                    }
+                }
+                Err(err) => return Err(err),
+            };
+            let post = actions::post::get_post_by_id(&conn, reaction.post_id)?;
+            let community = actions::actor::get_actor_by_id(&conn, post.community_id)?;
+            announce_to_followers(&conn, &local_domains, &community, &activity)?;
+            Ok(Some(reaction))
+        })
+    })
+    .await
+    .map_err(|_e| ActionError::InternalError)?
+}
@@ -0,0 +1,189 @@
+use crate::apub::models::Activity;
+use crate::db::actions;
+use crate::db::actions::vote::{get_vote, remove_vote, set_vote};
+use crate::db::models::{Actor, Post, PostView, Vote, VOTE_DOWN, VOTE_UP};
+use crate::errors::{ActionError, ActionResult};
+use crate::state::AppState;
+use super::community::announce_to_followers;
+use diesel::{Connection, PgConnection};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use tokio;
+
+/// The score of a vote sent as an activity of type `kind`, Like or Dislike.
+pub fn vote_score(kind: &str) -> Option<i16> {
+    match kind {
+        "Like" => Some(VOTE_UP),
+        "Dislike" => Some(VOTE_DOWN),
+        _ => None,
+    }
+}
+
+/// The Like or Dislike which sent a vote, to be wrapped in Undo.
+fn vote_object(voter: &Actor, post: &Post, vote: &Vote) -> Value {
+    match &vote.uri {
+        Some(uri) => json!({
+            "id": uri,
+            "type": vote.activity_kind(),
+            "actor": voter.uri,
+            "object": post.uri
+        }),
+        None => json!({
+            "type": vote.activity_kind(),
+            "actor": voter.uri,
+            "object": post.uri
+        }),
+    }
+}
+
+/// Address an activity about a post to its community and its author, and queue it for delivery to
+/// those on other servers; a local community announces it to its followers in turn.
+fn publish_post_activity(
+    db: &PgConnection,
+    local_domains: &HashSet<String>,
+    actor: &Actor,
+    view: &PostView,
+    activity: &mut Activity,
+) -> ActionResult<()> {
+    activity.to = Some(json!([view.community.uri]));
+    activity.cc = Some(json!([view.author.uri]));
+    activity.audience = Some(json!(view.community.uri));
+
+    let mut inboxes = vec![];
+    for recipient in &[&view.community, &view.author] {
+        let inbox_uri = String::from(recipient.delivery_inbox_uri());
+        if !local_domains.contains(&recipient.domain) && !inboxes.contains(&inbox_uri) {
+            inboxes.push(inbox_uri);
+        }
+    }
+    actions::activity::publish_activity(db, actor, &inboxes, activity)?;
+    announce_to_followers(db, local_domains, &view.community, activity)?;
+    Ok(())
+}
+
+/// Vote on a post on behalf of a local actor, and queue the Like or Dislike for delivery.
+pub fn send_vote(
+    db: &PgConnection,
+    local_domains: &HashSet<String>,
+    voter: &Actor,
+    post: &Post,
+    score: i16,
+) -> ActionResult<Vote> {
+    let view = actions::post::get_post_view(db, post.clone())?;
+    let kind = if score > 0 { "Like" } else { "Dislike" };
+    let mut activity = Activity::new_local(kind, voter, json!(post.uri));
+    db.transaction::<Vote, ActionError, _>(|| {
+        let vote = set_vote(db, voter, post, score, Some(activity.id.as_str()))?;
+        publish_post_activity(db, local_domains, voter, &view, &mut activity)?;
+        Ok(vote)
+    })
+}
+
+/// Withdraw the vote of a local actor on a post, and queue an Undo of the vote for delivery.
+pub fn send_undo_vote(
+    db: &PgConnection,
+    local_domains: &HashSet<String>,
+    voter: &Actor,
+    post: &Post,
+) -> ActionResult<Vote> {
+    let view = actions::post::get_post_view(db, post.clone())?;
+    db.transaction::<Vote, ActionError, _>(|| {
+        let vote = remove_vote(db, voter, post)?;
+        let mut activity = Activity::new_local("Undo", voter, vote_object(voter, post, &vote));
+        publish_post_activity(db, local_domains, voter, &view, &mut activity)?;
+        Ok(vote)
+    })
+}
+
+/// Record a vote of `voter` on the post `post_uri`, sent with `activity`, a Like or a Dislike.
+///
+/// Votes on unknown posts, and on deleted or removed ones, are ignored and return None.
+pub async fn receive_vote(
+    app_state: &AppState,
+    voter: &Actor,
+    post_uri: &str,
+    activity: &Activity,
+) -> ActionResult<Option<Vote>> {
+    let score = vote_score(activity.kind.as_str()).ok_or(ActionError::InvalidForm)?;
+
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+    let local_domains = app_state.local_domains.clone();
+    let voter = voter.clone();
+    let post_uri = String::from(post_uri);
+    let activity = activity.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let post = match actions::post::get_post_by_uri(&conn, post_uri.as_str()) {
+            Ok(post) => post,
+            Err(ActionError::NotFound) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        if post.is_deleted() || post.is_removed {
+            return Ok(None);
+        }
+        let community = actions::actor::get_actor_by_id(&conn, post.community_id)?;
+        conn.transaction::<_, ActionError, _>(|| {
+            let vote = set_vote(&conn, &voter, &post, score, Some(activity.id.as_str()))?;
+            announce_to_followers(&conn, &local_domains, &community, &activity)?;
+            Ok(Some(vote))
+        })
+    })
+    .await
+    .map_err(|_e| ActionError::InternalError)?
+}
+
+/// Withdraw the vote of `voter` on the post `post_uri`, as it asked with `activity`, an Undo of
+/// `undone`, a vote with `score`.
+///
+/// Returns None if there is no such vote, such as when it was replaced by a vote of the other kind,
+/// or by a later one sent with another activity.
+pub async fn receive_undo_vote(
+    app_state: &AppState,
+    voter: &Actor,
+    post_uri: &str,
+    score: i16,
+    undone: &Activity,
+    activity: &Activity,
+) -> ActionResult<Option<Vote>> {
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+    let local_domains = app_state.local_domains.clone();
+    let voter = voter.clone();
+    let post_uri = String::from(post_uri);
+    let undone = undone.clone();
+    let activity = activity.clone();
+
+    tokio::task::spawn_blocking(move || {
+        apply_undo_vote(&conn, &local_domains, &voter, post_uri.as_str(), score, &undone, &activity)
+    })
+    .await
+    .map_err(|_e| ActionError::InternalError)?
+}
+
+/// The blocking part of `receive_undo_vote`.
+pub fn apply_undo_vote(
+    db: &PgConnection,
+    local_domains: &HashSet<String>,
+    voter: &Actor,
+    post_uri: &str,
+    score: i16,
+    undone: &Activity,
+    activity: &Activity,
+) -> ActionResult<Option<Vote>> {
+    let post = match actions::post::get_post_by_uri(db, post_uri) {
+        Ok(post) => post,
+        Err(ActionError::NotFound) => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    match get_vote(db, voter, &post) {
+        // A vote recorded without the id of its activity can't be told apart.
+        Ok(vote) if vote.score == score && vote.uri.as_deref().is_none_or(|uri| uri == undone.id) => (),
+        Ok(_) | Err(ActionError::NotFound) => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let community = actions::actor::get_actor_by_id(db, post.community_id)?;
+    db.transaction::<_, ActionError, _>(|| {
+        let vote = remove_vote(db, voter, &post)?;
+        announce_to_followers(db, local_domains, &community, activity)?;
+        Ok(Some(vote))
+    })
+}
@@ -0,0 +1,35 @@
+use crate::apub::rsa::KeyPair;
+use openssl::pkey::{Id, PKey};
+
+/// Multicodec prefix of an Ed25519 public key in a `publicKeyMultibase`.
+const ED25519_PUB_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+pub fn generate_key_pair_pem() -> Option<KeyPair> {
+    let key = PKey::generate_ed25519().ok()?;
+    let private = String::from_utf8(key.private_key_to_pem_pkcs8().ok()?).ok()?;
+    let public = String::from_utf8(key.public_key_to_pem().ok()?).ok()?;
+    Some(KeyPair { public, private })
+}
+
+/// The `publicKeyMultibase` of an Ed25519 public key of a Multikey (FEP-521a): the key prefixed
+/// by its multicodec, in base58btc.
+pub fn public_key_multibase(public_key_pem: &str) -> Option<String> {
+    let key = PKey::public_key_from_pem(public_key_pem.as_bytes()).ok()?;
+    if key.id() != Id::ED25519 {
+        return None;
+    }
+    let mut bytes = ED25519_PUB_MULTICODEC.to_vec();
+    bytes.extend(key.raw_public_key().ok()?);
+    Some(format!("z{}", bs58::encode(bytes).into_string()))
+}
+
+/// The Ed25519 public key in PEM of a `publicKeyMultibase`, or None if it is not one.
+pub fn public_key_pem_from_multibase(multibase: &str) -> Option<String> {
+    let encoded = multibase.strip_prefix('z')?;
+    let bytes = bs58::decode(encoded).into_vec().ok()?;
+    if bytes.len() != ED25519_PUB_MULTICODEC.len() + 32 || !bytes.starts_with(&ED25519_PUB_MULTICODEC) {
+        return None;
+    }
+    let key = PKey::public_key_from_raw_bytes(&bytes[ED25519_PUB_MULTICODEC.len()..], Id::ED25519).ok()?;
+    String::from_utf8(key.public_key_to_pem().ok()?).ok()
+}
@@ -12,6 +12,27 @@ pub fn empty_string_or_none(value: String) -> Option<String> {
     }
 }
 
+/// Parse an xsd:dateTime property such as `published`.
+pub fn parse_datetime(value: &str) -> Option<chrono::NaiveDateTime> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.naive_utc())
+}
+
+/// Format a timestamp stored in UTC as an xsd:dateTime.
+pub fn format_datetime(value: &chrono::NaiveDateTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from_utc(*value, chrono::Utc)
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// Whether two URIs are served by the same host.
+pub fn is_same_origin(uri1: &str, uri2: &str) -> bool {
+    match (url::Url::parse(uri1), url::Url::parse(uri2)) {
+        (Ok(url1), Ok(url2)) => url1.host_str().is_some() && url1.host_str() == url2.host_str(),
+        _ => false,
+    }
+}
+
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug, Eq, PartialEq)]
@@ -1,5 +1,9 @@
+use crate::apub::serializers::get_context;
+use crate::db;
+use chrono::{SecondsFormat, Utc};
+use openssl;
 use serde::{Deserialize, Serialize};
-use serde_json::{self, Value};
+use serde_json::{self, json, Value};
 
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -14,9 +18,135 @@ pub struct Activity {
     pub id: String,
     pub actor: String,
     pub object: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub to: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cc: Option<Value>,
+    /// The community the activity happens in, see FEP-1b12.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<Value>,
+    /// The emoji of an EmojiReact, or of a Like as Misskey sends reactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<Value>,
+}
+
+/// The special collection addressing everyone.
+pub const PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+
+/// Collect the URIs of an addressing property, which may be a single value or an array,
+/// made of URIs or of objects with an id.
+pub fn uri_list(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::String(s)) => vec![s.clone()],
+        Some(Value::Object(map)) => map
+            .get("id")
+            .and_then(|id| id.as_str())
+            .map(|id| vec![String::from(id)])
+            .unwrap_or_default(),
+        Some(Value::Array(values)) => values.iter().flat_map(|v| uri_list(Some(v))).collect(),
+        _ => vec![],
+    }
+}
+
+/// Whether an addressing URI means the public collection, in any of its spellings.
+pub fn is_public_uri(uri: &str) -> bool {
+    uri == PUBLIC || uri == "as:Public" || uri == "Public"
+}
+
+impl Activity {
+    /// URIs the activity is addressed to, in `to` and `cc`.
+    pub fn recipients(&self) -> Vec<String> {
+        let mut recipients = uri_list(self.to.as_ref());
+        recipients.extend(uri_list(self.cc.as_ref()));
+        recipients
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.recipients().iter().any(|uri| is_public_uri(uri))
+    }
+
+    /// URIs the activity is addressed to, in `to`, `cc` and `audience`, along with those its
+    /// object is addressed to when it is embedded.
+    pub fn addressees(&self) -> Vec<String> {
+        let mut addressees = self.recipients();
+        addressees.extend(uri_list(self.audience.as_ref()));
+        for property in &["to", "cc", "audience"] {
+            addressees.extend(uri_list(self.object.get(property)));
+        }
+        let mut seen = std::collections::HashSet::new();
+        addressees.retain(|uri| seen.insert(uri.clone()));
+        addressees
+    }
+
+    /// Build an activity published now by a local actor, with a newly generated id.
+    pub fn new_local(kind: &str, actor: &db::models::Actor, object: Value) -> Activity {
+        Activity {
+            context: Some(get_context()),
+            kind: String::from(kind),
+            id: generate_local_uri(actor.domain.as_str(), "activities"),
+            actor: actor.uri.clone(),
+            object,
+            published: Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)),
+            to: None,
+            cc: None,
+            audience: None,
+            content: None,
+            tag: None,
+        }
+    }
+
+    /// Whether the id of the activity is served by the host of its actor; an actor can't claim
+    /// the ids of activities on other servers.
+    pub fn has_own_id(&self) -> bool {
+        super::is_same_origin(&self.id, &self.actor)
+    }
+
+    /// Whether the actor of an Update may change `object`: an actor may only update itself, and a
+    /// post may only be edited by its author.
+    pub fn may_update(&self, object: &Value) -> bool {
+        let owner = match object["type"].as_str().unwrap_or_default() {
+            "Person" | "Group" | "Service" | "Application" => &object["id"],
+            kind if POST_KINDS.contains(&kind) => &object["attributedTo"],
+            _ => return false,
+        };
+        owner.as_str() == Some(self.actor.as_str())
+    }
+
+    /// The emoji an EmojiReact or a Like with content reacts with; other Likes are votes.
+    pub fn reaction_content(&self) -> Option<&str> {
+        match (self.kind.as_str(), self.content.as_deref()) {
+            ("EmojiReact", Some(content)) | ("Like", Some(content)) if !content.trim().is_empty() => Some(content),
+            _ => None,
+        }
+    }
+
+    /// The image of the custom emoji of a reaction, from the Emoji in its tags.
+    pub fn reaction_emoji_url(&self) -> Option<String> {
+        let content = self.reaction_content()?;
+        let tags = match &self.tag {
+            Some(Value::Array(tags)) => tags.clone(),
+            Some(tag) => vec![tag.clone()],
+            None => vec![],
+        };
+        tags.iter()
+            .filter(|tag| tag["type"] == "Emoji" && tag["name"] == content)
+            .find_map(|tag| match &tag["icon"] {
+                Value::String(url) => Some(url.clone()),
+                icon => icon["url"].as_str().map(String::from),
+            })
+    }
+}
+
+/// Generate a unique URI such as `https://<domain>/activities/<random hex>` for a local object.
+pub fn generate_local_uri(domain: &str, slug: &str) -> String {
+    let mut buf = [0u8; 16];
+    openssl::rand::rand_bytes(&mut buf).expect("failed to generate random bytes");
+    let hex: String = buf.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("https://{}/{}/{}", domain, slug, hex)
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -27,19 +157,130 @@ pub struct Object {
     #[serde(rename = "type")]
     pub kind: String,
     pub id: String,
-    pub published: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
     pub attributed_to: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub in_reply_to: Option<String>,
+    /// Title of a thread.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     pub summary: Option<String>,
+    #[serde(default)]
     pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<Value>,
+    #[serde(default)]
     pub to: Value,
+    #[serde(default)]
     pub cc: Value,
+    /// The community a post belongs to, see FEP-1b12.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<Value>,
     pub tag: Option<Vec<Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub attachment: Option<Vec<Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sensitive: Option<bool>,
+    /// Collections of the upvotes and downvotes of a post, only counted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub likes: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dislikes: Option<Value>,
+    /// Collection of the Announces of a post by people, only counted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shares: Option<Value>,
+    /// Emoji reactions to a post, counted per emoji.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reactions: Option<Value>,
+}
+
+/// Object types stored as threads and comments.
+pub const POST_KINDS: [&str; 3] = ["Note", "Page", "Article"];
+
+impl Object {
+    /// URIs the object is addressed to, in `to` and `cc`.
+    pub fn recipients(&self) -> Vec<String> {
+        let mut recipients = uri_list(Some(&self.to));
+        recipients.extend(uri_list(Some(&self.cc)));
+        recipients
+    }
+
+    pub fn is_post(&self) -> bool {
+        POST_KINDS.contains(&self.kind.as_str())
+    }
+
+    /// The first link of `url`, which may be a single URI, a Link or an array of them.
+    pub fn url_string(&self) -> Option<String> {
+        match &self.url {
+            Some(Value::Object(link)) => link.get("href").and_then(|href| href.as_str()).map(String::from),
+            url => uri_list(url.as_ref()).into_iter().next(),
+        }
+    }
+}
+
+impl From<&db::models::PostView> for Object {
+    fn from(view: &db::models::PostView) -> Self {
+        let post = &view.post;
+        Object {
+            context: Some(get_context()),
+            kind: post.kind.clone(),
+            id: post.uri.clone(),
+            published: Some(super::format_datetime(&post.published)),
+            updated: post.updated_at.as_ref().map(super::format_datetime),
+            attributed_to: view.author.uri.clone(),
+            in_reply_to: view.in_reply_to.as_ref().map(|parent| parent.uri.clone()),
+            name: post.title.clone(),
+            summary: None,
+            content: post.content.clone(),
+            url: post.url.clone().map(Value::String),
+            to: json!([PUBLIC]),
+            cc: json!([view.community.uri]),
+            audience: Some(Value::String(view.community.uri.clone())),
+            tag: None,
+            attachment: None,
+            sensitive: None,
+            likes: Some(json!({"type": "Collection", "totalItems": post.upvotes})),
+            dislikes: Some(json!({"type": "Collection", "totalItems": post.downvotes})),
+            shares: Some(json!({"type": "Collection", "totalItems": post.shares})),
+            reactions: Some(Value::Array(view.reactions.iter().map(reaction_count_json).collect())),
+        }
+    }
+}
+
+/// A count of reactions with an emoji, along with the Emoji tag of a custom one.
+fn reaction_count_json(reaction: &db::models::ReactionCount) -> Value {
+    match &reaction.emoji_url {
+        Some(emoji_url) => json!({
+            "content": reaction.content,
+            "count": reaction.count,
+            "tag": [{
+                "type": "Emoji",
+                "name": reaction.content,
+                "icon": {"type": "Image", "url": emoji_url},
+            }],
+        }),
+        None => json!({
+            "content": reaction.content,
+            "count": reaction.count,
+        }),
+    }
+}
+
+/// The tombstone served in place of a deleted or removed post; only deleted ones tell when.
+pub fn post_tombstone(post: &db::models::Post) -> Value {
+    let mut tombstone = json!({
+        "@context": get_context(),
+        "type": "Tombstone",
+        "id": post.uri,
+        "formerType": post.kind,
+    });
+    if let Some(deleted_at) = &post.deleted_at {
+        tombstone["deleted"] = Value::String(super::format_datetime(deleted_at));
+    }
+    tombstone
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -1,4 +1,4 @@
-use super::Activity;
+use crate::apub::ed25519;
 use crate::apub::username::*;
 use crate::db;
 use crate::errors;
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::{self, json};
 use std::convert::TryFrom;
 use crate::apub::serializers::get_context;
-use super::empty_string_or_none;
+use super::{empty_string_or_none, is_same_origin};
 use crate::apub::webfinger;
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -36,28 +36,102 @@ pub struct Actor {
     pub manually_approves_followers: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub suspended: Option<bool>,
+    /// The moderators collection of a Group, as Lemmy does.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attributed_to: Option<serde_json::Value>,
+    /// Multikeys of the actor other than its RSA key (FEP-521a).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assertion_method: Option<serde_json::Value>,
+}
+
+/// keyId of the main key of an actor, which is used to sign HTTP requests.
+pub fn main_key_id(actor_uri: &str) -> String {
+    format!("{}#main-key", actor_uri)
+}
+
+/// keyId of the Ed25519 key of a local actor, published as a Multikey in its `assertionMethod`.
+pub fn ed25519_key_id(actor_uri: &str) -> String {
+    format!("{}#ed25519-key", actor_uri)
+}
+
+/// URI of the collection of moderators of a community.
+pub fn moderators_uri(actor_uri: &str) -> String {
+    format!("{}/moderators", actor_uri)
 }
 
 impl Actor {
-    pub fn get_public_key_pem(&self) -> Option<String> {
-        if self.public_key["owner"] == json!(self.id) && self.public_key["id"] == json!(format!("{}#main-key", self.id)) {
-            self.public_key["publicKeyPem"].as_str().map(String::from)
-        } else {
-            None
+    /// `endpoints.sharedInbox`, if the server of the actor has one.
+    pub fn shared_inbox_uri(&self) -> Option<String> {
+        self.endpoints
+            .as_ref()
+            .and_then(|endpoints| endpoints["sharedInbox"].as_str())
+            .map(String::from)
+    }
+
+    /// Keys owned by the actor, as pairs of keyId and PEM: first those in `publicKey`, which may be
+    /// a single key or an array of them, then the Ed25519 Multikeys in `assertionMethod`.
+    ///
+    /// Keys served by another host than the actor are left out, an actor can't claim them.
+    pub fn public_keys(&self) -> Vec<(String, String)> {
+        let as_list = |value: &serde_json::Value| match value {
+            serde_json::Value::Array(values) => values.clone(),
+            serde_json::Value::Null => vec![],
+            value => vec![value.clone()],
+        };
+        let public_keys = as_list(&self.public_key)
+            .into_iter()
+            .filter(|key| key["owner"] == json!(self.id))
+            .filter_map(|key| match (key["id"].as_str(), key["publicKeyPem"].as_str()) {
+                (Some(key_id), Some(pem)) => Some((String::from(key_id), String::from(pem))),
+                _ => None,
+            });
+        let multikeys = as_list(self.assertion_method.as_ref().unwrap_or(&serde_json::Value::Null))
+            .into_iter()
+            .filter(|key| key["type"] == json!("Multikey") && key["controller"] == json!(self.id))
+            .filter_map(|key| {
+                let pem = key["publicKeyMultibase"].as_str().and_then(ed25519::public_key_pem_from_multibase)?;
+                Some((String::from(key["id"].as_str()?), pem))
+            });
+        public_keys
+            .chain(multikeys)
+            .filter(|(key_id, _pem)| is_same_origin(key_id, &self.id))
+            .collect()
+    }
+
+    /// Publish the Ed25519 keys of a local actor in its `assertionMethod`.
+    pub fn with_keys(self, keys: &[db::models::Key]) -> Actor {
+        let multikeys: Vec<serde_json::Value> = keys
+            .iter()
+            .filter_map(|key| {
+                Some(json!({
+                    "id": key.uri,
+                    "type": "Multikey",
+                    "controller": self.id,
+                    "publicKeyMultibase": ed25519::public_key_multibase(key.public_key_pem.as_str())?,
+                }))
+            })
+            .collect();
+        if multikeys.is_empty() {
+            return self;
+        }
+        let context = match self.context {
+            Some(serde_json::Value::Array(mut context)) => {
+                context.push(json!("https://w3id.org/security/multikey/v1"));
+                Some(serde_json::Value::Array(context))
+            }
+            context => context,
+        };
+        Actor {
+            context,
+            assertion_method: Some(json!(multikeys)),
+            ..self
         }
     }
-}
 
-#[derive(Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Outbox {
-    #[serde(rename = "@context")]
-    pub context: serde_json::Value,
-    #[serde(rename = "type")]
-    pub kind: String,
-    pub id: String,
-    pub total_items: i64,
-    pub ordered_items: Vec<Activity>,
+    /// The main key of the actor, which is the first one.
+    pub fn get_public_key_pem(&self) -> Option<String> {
+        self.public_keys().into_iter().next().map(|(_key_id, pem)| pem)
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -92,7 +166,7 @@ impl From<&db::models::Actor> for Actor {
             inbox: actor_db.inbox_uri.clone(),
             outbox: actor_db.outbox_uri.clone(),
             public_key: json!({
-                "id": format!("{}#main-key", actor_db.uri),
+                "id": main_key_id(actor_db.uri.as_str()),
                 "owner": actor_db.uri,
                 "publicKeyPem": actor_db.public_key_pem
             }),
@@ -105,6 +179,11 @@ impl From<&db::models::Actor> for Actor {
                 "url": actor_db.avatar_url
             })),
             suspended: Some(actor_db.is_suspended.clone()),
+            attributed_to: match db::models::ActorType::from(actor_db) {
+                db::models::ActorType::Group => Some(json!(moderators_uri(actor_db.uri.as_str()))),
+                _ => None,
+            },
+            assertion_method: None,
         }
     }
 }
@@ -147,6 +226,7 @@ impl TryFrom<&Actor> for db::models::NewActor {
             is_locked,
             is_suspended,
             is_silenced: false,
+            shared_inbox_uri: actor_ap.shared_inbox_uri(),
         })
     }
 }
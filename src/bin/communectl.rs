@@ -1,5 +1,7 @@
 use commune::db::establish_connection;
-use commune::db::actions::user::create_user;
+use commune::db::actions::community::create_community;
+use commune::db::actions::key::create_missing_local_ed25519_keys;
+use commune::db::actions::user::{create_user, get_user_actor_by_username_domain};
 
 use getopts::Options;
 use rpassword;
@@ -14,11 +16,27 @@ fn help(subcmd: &str) {
             "#
             );
         }
+        "community" => {
+            println!(
+                r#"Usage:
+            communectl community create ,,,
+            "#
+            );
+        }
+        "keys" => {
+            println!(
+                r#"Usage:
+            communectl keys backfill
+            "#
+            );
+        }
         _ => {
             println!(
                 r#"Usage:
             communectl help <subcommand>
             communectl user ...
+            communectl community ...
+            communectl keys ...
             "#
             );
         }
@@ -39,6 +57,16 @@ fn main() {
             Some(_) => help("user"),
             None => help("user"),
         },
+        Some("community") => match args.get(2).as_ref().map(|s| &s[..]) {
+            Some("create") => subcmd_community_create(args),
+            Some(_) => help("community"),
+            None => help("community"),
+        },
+        Some("keys") => match args.get(2).as_ref().map(|s| &s[..]) {
+            Some("backfill") => subcmd_keys_backfill(),
+            Some(_) => help("keys"),
+            None => help("keys"),
+        },
         _ => help(""),
     }
 }
@@ -75,3 +103,58 @@ fn subcmd_user_create(args: Vec<String>) {
         };
     }
 }
+
+fn subcmd_community_create(args: Vec<String>) {
+    let mut opts = Options::new();
+    opts.reqopt("n", "name", "community name (without domain)", "NAME");
+    opts.reqopt("d", "domain", "community domain", "DOMAIN");
+    opts.reqopt("o", "owner", "username of the owner, on the same domain", "USERNAME");
+    opts.optopt("t", "title", "display name, defaults to the name", "TITLE");
+    opts.optopt("s", "description", "description of the community", "DESCRIPTION");
+    let args_usage = args[0..3].to_vec();
+    let matches = match opts.parse(&args[3..]) {
+        Ok(m) => m,
+        Err(_f) => {
+            println!("{}", help_opts(&args_usage, &opts));
+            panic!("required opt");
+        },
+    };
+    let name = matches.opt_str("n").expect(&help_opts(&args_usage, &opts));
+    let domain = matches.opt_str("d").expect(&help_opts(&args_usage, &opts));
+    let owner = matches.opt_str("o").expect(&help_opts(&args_usage, &opts));
+    let title = matches.opt_str("t").unwrap_or_else(|| name.clone());
+    let description = matches.opt_str("s").unwrap_or_default();
+
+    let conn = establish_connection();
+    let owner = match get_user_actor_by_username_domain(&conn, owner.as_str(), domain.as_str()) {
+        Ok(owner) => owner,
+        Err(e) => {
+            eprintln!("owner: {}", e);
+            return;
+        }
+    };
+    match create_community(
+        &conn,
+        name.as_str(),
+        domain.as_str(),
+        title.as_str(),
+        description.as_str(),
+        "und",
+        &owner,
+    ) {
+        Ok(_) => (),
+        Err(e) => eprintln!("{}", e),
+    };
+}
+
+fn subcmd_keys_backfill() {
+    let conn = establish_connection();
+    match create_missing_local_ed25519_keys(&conn) {
+        Ok(keys) => {
+            for key in keys {
+                println!("{}", key.uri);
+            }
+        }
+        Err(e) => eprintln!("{}", e),
+    };
+}
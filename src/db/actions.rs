@@ -1,3 +1,12 @@
 pub mod actor;
 pub mod user;
-pub mod follow;
\ No newline at end of file
+pub mod follow;
+pub mod delivery;
+pub mod activity;
+pub mod post;
+pub mod community;
+pub mod key;
+pub mod moderation;
+pub mod vote;
+pub mod reaction;
+pub mod share;
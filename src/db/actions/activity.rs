@@ -0,0 +1,109 @@
+use crate::apub::models::{parse_datetime, Activity as ActivityS};
+use crate::db::actions::delivery::enqueue_activity;
+use crate::db::models::{Activity, Actor, NewActivity};
+use crate::db::schema;
+use crate::errors::{ActionError, ActionResult};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+pub const PAGE_SIZE: i64 = 12;
+
+/// Store an activity published by a local actor.
+pub fn insert_activity(db: &PgConnection, actor: &Actor, activity: &ActivityS) -> ActionResult<Activity> {
+    let published = activity
+        .published
+        .as_deref()
+        .and_then(parse_datetime)
+        .unwrap_or_else(|| Utc::now().naive_utc());
+    let new_activity = NewActivity {
+        uri: activity.id.clone(),
+        actor_id: actor.id,
+        kind: activity.kind.clone(),
+        body: serde_json::to_string(activity).map_err(|_e| ActionError::InternalError)?,
+        is_public: activity.is_public(),
+        published,
+    };
+
+    diesel::insert_into(schema::activities::table)
+        .values(&new_activity)
+        .get_result::<Activity>(db)
+        .map_err(|_e| ActionError::InsertError)
+}
+
+/// Store an activity published by a local actor, and queue it for delivery to `inbox_uris`.
+pub fn publish_activity(
+    db: &PgConnection,
+    actor: &Actor,
+    inbox_uris: &[String],
+    activity: &ActivityS,
+) -> ActionResult<Activity> {
+    db.transaction::<Activity, ActionError, _>(|| {
+        let stored = insert_activity(db, actor, activity)?;
+        enqueue_activity(db, actor, inbox_uris, activity)?;
+        Ok(stored)
+    })
+}
+
+pub fn get_activity_by_uri(db: &PgConnection, uri_in: &str) -> ActionResult<Activity> {
+    use schema::activities::dsl::*;
+    activities
+        .filter(uri.eq(uri_in))
+        .first(db)
+        .map_err(ActionError::from)
+}
+
+/// Public activities of an actor, newest first.
+pub fn actor_get_outbox(db: &PgConnection, actor: &Actor, page: i64) -> ActionResult<Vec<Activity>> {
+    use schema::activities::dsl::*;
+    activities
+        .filter(actor_id.eq(actor.id))
+        .filter(is_public.eq(true))
+        .order((published.desc(), id.desc()))
+        .limit(PAGE_SIZE)
+        .offset(PAGE_SIZE * (page - 1))
+        .load(db)
+        .map_err(|_e| ActionError::NotFound)
+}
+
+pub fn actor_count_outbox(db: &PgConnection, actor: &Actor) -> ActionResult<i64> {
+    use diesel::dsl::count_star;
+    use schema::activities::dsl::*;
+    activities
+        .filter(actor_id.eq(actor.id))
+        .filter(is_public.eq(true))
+        .select(count_star())
+        .first(db)
+        .map_err(|_e| ActionError::NotFound)
+}
+
+/// Claim an activity received from another server before applying it, so that it is applied only
+/// once even if delivered several times at once.
+///
+/// Returns false if the activity was claimed already.
+pub fn claim_activity(db: &PgConnection, uri_in: &str) -> ActionResult<bool> {
+    use schema::processed_activities::dsl::*;
+    diesel::insert_into(processed_activities)
+        .values((uri.eq(uri_in), processed_at.eq(Utc::now().naive_utc())))
+        .on_conflict_do_nothing()
+        .execute(db)
+        .map(|count| count > 0)
+        .map_err(|_e| ActionError::InsertError)
+}
+
+/// Give up the claim on an activity which failed to apply, so that it can be delivered again.
+pub fn release_activity(db: &PgConnection, uri_in: &str) -> ActionResult<()> {
+    use schema::processed_activities::dsl::*;
+    diesel::delete(processed_activities.filter(uri.eq(uri_in)))
+        .execute(db)
+        .map(|_count| ())
+        .map_err(ActionError::from)
+}
+
+/// Forget the activities processed before `before`, returning how many were forgotten.
+pub fn prune_processed_activities(db: &PgConnection, before: chrono::NaiveDateTime) -> ActionResult<usize> {
+    use schema::processed_activities::dsl::*;
+    diesel::delete(processed_activities.filter(processed_at.lt(before)))
+        .execute(db)
+        .map_err(ActionError::from)
+}
@@ -1,7 +1,10 @@
+use crate::apub::models::is_same_origin;
 use crate::db::models::{Actor, NewActor};
 use crate::errors::{ActionError, ActionResult};
+use chrono::Utc;
 use diesel::prelude::*;
 use diesel::PgConnection;
+use std::collections::HashSet;
 use validator::Validate;
 
 pub fn get_actor_by_username_domain(
@@ -20,6 +23,21 @@ pub fn get_actor_by_username_domain(
         })
 }
 
+/// Like `get_actor_by_username_domain`, but never resolves a Group.
+pub fn get_person_by_username_domain(
+    db: &PgConnection,
+    username_in: &str,
+    domain_in: &str,
+) -> ActionResult<Actor> {
+    use crate::db::schema::actors::dsl::*;
+    actors
+        .filter(username.eq(String::from(username_in)))
+        .filter(domain.eq(String::from(domain_in)))
+        .filter(kind.ne("Group"))
+        .first(db)
+        .map_err(ActionError::from)
+}
+
 pub fn get_actor_by_uri(db: &PgConnection, uri_in: &str) -> ActionResult<Actor> {
     use crate::db::schema::actors::dsl::*;
     actors
@@ -31,6 +49,31 @@ pub fn get_actor_by_uri(db: &PgConnection, uri_in: &str) -> ActionResult<Actor>
         })
 }
 
+/// The actor whose followers collection is `followers_uri_in`.
+///
+/// Only an actor of the host serving the collection can claim it, and if several do, none of
+/// them is trusted with it.
+pub fn get_actor_by_followers_uri(db: &PgConnection, followers_uri_in: &str) -> ActionResult<Actor> {
+    use crate::db::schema::actors::dsl::*;
+    let mut owners: Vec<Actor> = actors
+        .filter(followers_uri.eq(followers_uri_in))
+        .load::<Actor>(db)
+        .map_err(ActionError::from)?
+        .into_iter()
+        .filter(|actor| is_same_origin(&actor.uri, followers_uri_in))
+        .collect();
+    match owners.len() {
+        0 => Err(ActionError::NotFound),
+        1 => Ok(owners.remove(0)),
+        _ => Err(ActionError::InvalidForm),
+    }
+}
+
+pub fn get_actor_by_id(db: &PgConnection, id_in: i64) -> ActionResult<Actor> {
+    use crate::db::schema::actors::dsl::*;
+    actors.find(id_in).first(db).map_err(ActionError::from)
+}
+
 pub fn insert_new_actor(db: &PgConnection, new_actor: NewActor) -> ActionResult<Actor> {
     use crate::db::schema::actors::dsl::*;
     new_actor.validate().map_err(|_e| ActionError::InvalidForm)?;
@@ -40,4 +83,90 @@ pub fn insert_new_actor(db: &PgConnection, new_actor: NewActor) -> ActionResult<
         .map_err(|_e| {
             ActionError::InsertError
         })
-}
\ No newline at end of file
+}
+
+/// Overwrite a remote actor with a newer version of its document, such as after a key rotation.
+///
+/// The uri, username, domain and the local `is_silenced` flag are kept, while `is_suspended`
+/// follows `suspended` in the document.
+pub fn update_actor(db: &PgConnection, actor: &Actor, new_actor: &NewActor) -> ActionResult<Actor> {
+    use crate::db::schema::actors::dsl::*;
+    new_actor.validate().map_err(|_e| ActionError::InvalidForm)?;
+    diesel::update(actor)
+        .set((
+            url.eq(&new_actor.url),
+            kind.eq(&new_actor.kind),
+            name.eq(new_actor.name.clone().unwrap_or_default()),
+            summary.eq(new_actor.summary.clone().unwrap_or_default()),
+            avatar_url.eq(new_actor.avatar_url.clone().unwrap_or_default()),
+            inbox_uri.eq(&new_actor.inbox_uri),
+            outbox_uri.eq(&new_actor.outbox_uri),
+            followers_uri.eq(&new_actor.followers_uri),
+            following_uri.eq(&new_actor.following_uri),
+            shared_inbox_uri.eq(&new_actor.shared_inbox_uri),
+            public_key_pem.eq(&new_actor.public_key_pem),
+            is_locked.eq(new_actor.is_locked),
+            is_suspended.eq(new_actor.is_suspended),
+            updated_at.eq(Some(Utc::now().naive_utc())),
+        ))
+        .get_result(db)
+        .map_err(ActionError::from)
+}
+
+/// Mark a remote actor as deleted, dropping its follows both ways, its votes, reactions and shares,
+/// and replacing its posts with tombstones.
+///
+/// The row is kept, so that the threads it took part in keep their structure.
+pub fn delete_actor(db: &PgConnection, actor: &Actor) -> ActionResult<Actor> {
+    use crate::db::schema::{actors, follows};
+    db.transaction::<Actor, ActionError, _>(|| {
+        diesel::delete(follows::table.filter(follows::follower_id.eq(actor.id).or(follows::following_id.eq(actor.id))))
+            .execute(db)
+            .map_err(ActionError::from)?;
+        super::vote::actor_remove_votes(db, actor)?;
+        super::reaction::actor_remove_reactions(db, actor)?;
+        super::share::actor_remove_shares(db, actor)?;
+        super::post::author_tombstone_posts(db, actor)?;
+        diesel::update(actor)
+            .set(actors::deleted_at.eq(Some(Utc::now().naive_utc())))
+            .get_result(db)
+            .map_err(ActionError::from)
+    })
+}
+
+/// Mark an actor as up to date without changing it, such as when refreshing it failed.
+pub fn touch_actor(db: &PgConnection, actor: &Actor) -> ActionResult<Actor> {
+    use crate::db::schema::actors::dsl::*;
+    diesel::update(actor)
+        .set(updated_at.eq(Some(Utc::now().naive_utc())))
+        .get_result(db)
+        .map_err(ActionError::from)
+}
+
+/// Remote actors not refreshed since `before`, the stalest first.
+pub fn get_stale_remote_actors(
+    db: &PgConnection,
+    local_domains: &HashSet<String>,
+    before: chrono::NaiveDateTime,
+    limit: i64,
+) -> ActionResult<Vec<Actor>> {
+    use crate::db::schema::actors::dsl::*;
+    actors
+        .filter(domain.ne_all(local_domains.iter().cloned().collect::<Vec<String>>()))
+        .filter(updated_at.is_null().or(updated_at.lt(before)))
+        .filter(deleted_at.is_null())
+        .order(updated_at.asc().nulls_first())
+        .limit(limit)
+        .load(db)
+        .map_err(ActionError::from)
+}
+
+/// A local actor along with its private key, held either by a user or by a community.
+pub fn get_local_actor_with_key(db: &PgConnection, actor_id_in: i64) -> ActionResult<(Actor, String)> {
+    match super::user::get_user_actor_by_actor_id(db, actor_id_in) {
+        Ok(user_actor) => Ok((user_actor.actor, user_actor.user.private_key_pem)),
+        Err(ActionError::NotFound) => super::community::get_community_actor_by_actor_id(db, actor_id_in)
+            .map(|community_actor| (community_actor.actor, community_actor.community.private_key_pem)),
+        Err(e) => Err(e),
+    }
+}
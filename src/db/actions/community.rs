@@ -0,0 +1,116 @@
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+use crate::apub;
+use crate::db::actions::follow::follow_actor_by_uri;
+use crate::db::actions::key::{insert_local_key, set_actor_keys};
+use crate::db::schema;
+
+use crate::db::models::actor::{Actor, ActorType, NewActor, NewLocalActorBuilder};
+use crate::db::models::{Community, CommunityActor, UserActor};
+use crate::errors::{ActionError, ActionResult};
+
+use chrono::Utc;
+use validator::Validate;
+
+/// Create a local community, a Group actor with its own keypair, owned by a local user.
+pub fn create_community(
+    conn: &PgConnection,
+    name: &str,
+    domain: &str,
+    title: &str,
+    description: &str,
+    lang: &str,
+    owner: &UserActor,
+) -> ActionResult<CommunityActor> {
+    let keypair = match apub::rsa::generate_key_pair_pem() {
+        Some(pair) => pair,
+        None => return Err(ActionError::InternalError),
+    };
+    let ed25519_keypair = match apub::ed25519::generate_key_pair_pem() {
+        Some(pair) => pair,
+        None => return Err(ActionError::InternalError),
+    };
+
+    let new_actor = NewActor {
+        name: Some(String::from(title)),
+        summary: Some(String::from(description)),
+        lang: String::from(lang),
+        ..NewLocalActorBuilder {
+            username: name,
+            domain,
+            lang,
+            actor_type: ActorType::Group,
+            public_key_pem: keypair.public.as_str(),
+        }
+        .build()
+    };
+    new_actor.validate().map_err(|_e| ActionError::InvalidForm)?;
+
+    conn.transaction::<CommunityActor, ActionError, _>(|| {
+        let actor = diesel::insert_into(schema::actors::table)
+            .values(&new_actor)
+            .get_result::<Actor>(conn)
+            .map_err(|_| ActionError::InsertError)?;
+
+        let new_community = Community {
+            actor_id: actor.id,
+            owner_id: owner.actor.id,
+            private_key_pem: keypair.private,
+            created_at: Utc::now().naive_utc(),
+        };
+        let community = diesel::insert_into(schema::communities::table)
+            .values(&new_community)
+            .get_result::<Community>(conn)
+            .map_err(|_| ActionError::InsertError)?;
+        set_actor_keys(conn, &actor, &[(apub::models::main_key_id(actor.uri.as_str()), actor.public_key_pem.clone())])?;
+        insert_local_key(
+            conn,
+            &actor,
+            apub::models::ed25519_key_id(actor.uri.as_str()).as_str(),
+            ed25519_keypair.public.as_str(),
+            ed25519_keypair.private.as_str(),
+        )?;
+        follow_actor_by_uri(conn, owner.actor.uri.as_str(), actor.uri.as_str(), None)?;
+
+        Ok(CommunityActor { actor, community })
+    })
+}
+
+/// The Group actor named `name` on `domain`; other kinds of actors are not communities.
+pub fn get_community_by_name_domain(conn: &PgConnection, name_in: &str, domain_in: &str) -> ActionResult<Actor> {
+    use schema::actors::dsl::*;
+    actors
+        .filter(username.eq(name_in))
+        .filter(domain.eq(domain_in))
+        .filter(kind.eq("Group"))
+        .first(conn)
+        .map_err(ActionError::from)
+}
+
+pub fn get_community_actor_by_actor_id(conn: &PgConnection, actor_id_in: i64) -> ActionResult<CommunityActor> {
+    use schema::actors;
+    use schema::communities;
+    actors::table
+        .inner_join(communities::table)
+        .filter(actors::id.eq(actor_id_in))
+        .first::<(Actor, Community)>(conn)
+        .map(|(actor, community)| CommunityActor { actor, community })
+        .map_err(ActionError::from)
+}
+
+pub fn get_community_actor_by_name_domain(
+    conn: &PgConnection,
+    name: &str,
+    domain_in: &str,
+) -> ActionResult<CommunityActor> {
+    use schema::actors;
+    use schema::communities;
+    actors::table
+        .inner_join(communities::table)
+        .filter(actors::username.eq(name))
+        .filter(actors::domain.eq(domain_in))
+        .first::<(Actor, Community)>(conn)
+        .map(|(actor, community)| CommunityActor { actor, community })
+        .map_err(ActionError::from)
+}
@@ -0,0 +1,174 @@
+use crate::apub::models::Activity;
+use crate::db::models::{
+    Actor, Delivery, HostSignatureStyle, NewDelivery, DELIVERY_DELIVERED, DELIVERY_FAILED, DELIVERY_PENDING,
+};
+use crate::db::schema;
+use crate::errors::{ActionError, ActionResult};
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use diesel::PgConnection;
+use std::collections::HashSet;
+
+/// Delay before the first retry; doubled after every failed attempt.
+const RETRY_BASE_SECS: i64 = 30;
+/// Upper bound of the delay between two attempts.
+const RETRY_MAX_SECS: i64 = 6 * 60 * 60;
+
+/// Queue a signed POST of `body` to `inbox_uri`, on behalf of the local actor `actor_id`.
+pub fn enqueue_delivery(
+    db: &PgConnection,
+    actor_id: i64,
+    inbox_uri: &str,
+    body: &str,
+) -> ActionResult<Delivery> {
+    let now = Utc::now().naive_utc();
+    let new_delivery = NewDelivery {
+        actor_id,
+        inbox_uri: String::from(inbox_uri),
+        body: String::from(body),
+        next_attempt_at: now,
+        created_at: now,
+    };
+
+    diesel::insert_into(schema::deliveries::table)
+        .values(&new_delivery)
+        .get_result::<Delivery>(db)
+        .map_err(|_e| ActionError::InsertError)
+}
+
+/// Queue an activity published by a local actor, one delivery per inbox, even if the inbox
+/// is listed several times.
+pub fn enqueue_activity(
+    db: &PgConnection,
+    actor: &Actor,
+    inbox_uris: &[String],
+    activity: &Activity,
+) -> ActionResult<Vec<Delivery>> {
+    let body = serde_json::to_string(activity).map_err(|_e| ActionError::InternalError)?;
+    let mut seen = HashSet::new();
+    db.transaction::<Vec<Delivery>, ActionError, _>(|| {
+        inbox_uris
+            .iter()
+            .filter(|inbox_uri| seen.insert(inbox_uri.as_str()))
+            .map(|inbox_uri| enqueue_delivery(db, actor.id, inbox_uri.as_str(), body.as_str()))
+            .collect()
+    })
+}
+
+/// Take the next due delivery, and postpone it by `lease` so that other workers skip it
+/// while it is being sent.
+pub fn claim_due_delivery(db: &PgConnection, lease: Duration) -> ActionResult<Option<Delivery>> {
+    use schema::deliveries::dsl::*;
+    db.transaction::<Option<Delivery>, ActionError, _>(|| {
+        let now = Utc::now().naive_utc();
+        let delivery = deliveries
+            .filter(status.eq(DELIVERY_PENDING))
+            .filter(next_attempt_at.le(now))
+            .order(next_attempt_at.asc())
+            .for_update()
+            .skip_locked()
+            .first::<Delivery>(db)
+            .optional()?;
+
+        match delivery {
+            Some(delivery) => diesel::update(&delivery)
+                .set(next_attempt_at.eq(now + lease))
+                .get_result::<Delivery>(db)
+                .map(Some)
+                .map_err(ActionError::from),
+            None => Ok(None),
+        }
+    })
+}
+
+pub fn mark_delivered(db: &PgConnection, delivery: &Delivery, status_code: i32) -> ActionResult<Delivery> {
+    use schema::deliveries::dsl::*;
+    diesel::update(delivery)
+        .set((
+            status.eq(DELIVERY_DELIVERED),
+            attempts.eq(delivery.attempts + 1),
+            last_status_code.eq(Some(status_code)),
+            last_error.eq(None::<String>),
+            updated_at.eq(Some(Utc::now().naive_utc())),
+        ))
+        .get_result(db)
+        .map_err(ActionError::from)
+}
+
+/// Record a failed attempt, and schedule a retry with exponential backoff.
+///
+/// The delivery is given up if `permanent` is set, or if it has been queued for longer than
+/// `give_up_after`.
+pub fn mark_delivery_failed(
+    db: &PgConnection,
+    delivery: &Delivery,
+    status_code: Option<i32>,
+    error: &str,
+    permanent: bool,
+    give_up_after: Duration,
+) -> ActionResult<Delivery> {
+    use schema::deliveries::dsl::*;
+    let now = Utc::now().naive_utc();
+    let next = now + retry_delay(delivery.attempts);
+    let new_status = if permanent || next > delivery.created_at + give_up_after {
+        DELIVERY_FAILED
+    } else {
+        DELIVERY_PENDING
+    };
+
+    diesel::update(delivery)
+        .set((
+            status.eq(new_status),
+            attempts.eq(delivery.attempts + 1),
+            last_status_code.eq(status_code),
+            last_error.eq(Some(String::from(error))),
+            next_attempt_at.eq(next),
+            updated_at.eq(Some(now)),
+        ))
+        .get_result(db)
+        .map_err(ActionError::from)
+}
+
+/// Delay before retrying a delivery which has already failed `attempts` times.
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.clamp(0, 20) as u32;
+    Duration::seconds((RETRY_BASE_SECS << exponent).min(RETRY_MAX_SECS))
+}
+
+/// The signature style a host accepted, unless it was learnt before `since`.
+pub fn get_signature_style(db: &PgConnection, host_in: &str, since: chrono::NaiveDateTime) -> ActionResult<Option<String>> {
+    use schema::host_signature_styles::dsl::*;
+    host_signature_styles
+        .filter(host.eq(host_in))
+        .filter(updated_at.ge(since))
+        .select(style)
+        .first::<String>(db)
+        .optional()
+        .map_err(ActionError::from)
+}
+
+/// Remember the signature style a host accepted.
+pub fn set_signature_style(db: &PgConnection, host_in: &str, style_in: &str) -> ActionResult<HostSignatureStyle> {
+    use schema::host_signature_styles::dsl::*;
+    let host_signature_style = HostSignatureStyle {
+        host: String::from(host_in),
+        style: String::from(style_in),
+        updated_at: Utc::now().naive_utc(),
+    };
+    diesel::insert_into(host_signature_styles)
+        .values(&host_signature_style)
+        .on_conflict(host)
+        .do_update()
+        .set((style.eq(style_in), updated_at.eq(host_signature_style.updated_at)))
+        .get_result(db)
+        .map_err(|_e| ActionError::InsertError)
+}
+
+/// Forget the signature style a host accepted, once it rejects it.
+pub fn forget_signature_style(db: &PgConnection, host_in: &str) -> ActionResult<()> {
+    use schema::host_signature_styles::dsl::*;
+    diesel::delete(host_signature_styles.filter(host.eq(host_in)))
+        .execute(db)
+        .map(|_count| ())
+        .map_err(ActionError::from)
+}
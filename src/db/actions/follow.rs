@@ -1,10 +1,11 @@
 use crate::db::actions::actor::get_actor_by_uri;
-use crate::db::models::{Actor, Follow};
+use crate::db::models::{Actor, Follow, FOLLOW_FOLLOWER, FOLLOW_PENDING};
 use crate::db::schema;
 use crate::errors::{ActionError, ActionResult};
 use chrono::Utc;
 use diesel::prelude::*;
 use diesel::PgConnection;
+use std::collections::HashSet;
 
 pub const PAGE_SIZE: i64 = 12;
 
@@ -20,7 +21,7 @@ pub fn actor_get_followers(
             follows::table.on(follows::follower_id
                 .eq(actors::id)
                 .and(follows::following_id.eq(actor.id))
-                .and(follows::role.ne("pending"))),
+                .and(follows::role.ne(FOLLOW_PENDING))),
         )
         .order(follows::created_at.desc())
         .limit(PAGE_SIZE)
@@ -30,6 +31,45 @@ pub fn actor_get_followers(
         .map_err(|_e| ActionError::NotFound)
 }
 
+/// All followers of an actor at once, for delivering to them.
+pub fn actor_get_all_followers(db: &PgConnection, actor: &Actor) -> ActionResult<Vec<Actor>> {
+    use schema::actors;
+    use schema::follows;
+    let data = actors::table
+        .inner_join(
+            follows::table.on(follows::follower_id
+                .eq(actors::id)
+                .and(follows::following_id.eq(actor.id))
+                .and(follows::role.ne(FOLLOW_PENDING))),
+        )
+        .order(follows::created_at.asc())
+        .load(db);
+    data.map(|v: Vec<(Actor, Follow)>| v.into_iter().map(|(a, _f)| a).collect())
+        .map_err(ActionError::from)
+}
+
+/// Whether an actor has followers on a local domain, pending follows aside.
+pub fn actor_has_local_followers(
+    db: &PgConnection,
+    local_domains: &HashSet<String>,
+    actor: &Actor,
+) -> ActionResult<bool> {
+    use schema::actors;
+    use schema::follows;
+    let count: i64 = actors::table
+        .inner_join(
+            follows::table.on(follows::follower_id
+                .eq(actors::id)
+                .and(follows::following_id.eq(actor.id))
+                .and(follows::role.ne(FOLLOW_PENDING))),
+        )
+        .filter(actors::domain.eq_any(local_domains.iter().cloned().collect::<Vec<String>>()))
+        .count()
+        .get_result(db)
+        .map_err(ActionError::from)?;
+    Ok(count > 0)
+}
+
 pub fn actor_count_followers(db: &PgConnection, actor: &Actor) -> ActionResult<i64> {
     use diesel::dsl::count_star;
     use schema::actors;
@@ -39,39 +79,185 @@ pub fn actor_count_followers(db: &PgConnection, actor: &Actor) -> ActionResult<i
             follows::table.on(follows::follower_id
                 .eq(actors::id)
                 .and(follows::following_id.eq(actor.id))
-                .and(follows::role.ne("pending"))),
+                .and(follows::role.ne(FOLLOW_PENDING))),
         )
         .select(count_star())
         .first(db);
     data.map_err(|_e| ActionError::NotFound)
 }
 
+pub fn actor_get_following(
+    db: &PgConnection,
+    actor: &Actor,
+    page: i64,
+) -> ActionResult<Vec<Actor>> {
+    use schema::actors;
+    use schema::follows;
+    let data = actors::table
+        .inner_join(
+            follows::table.on(follows::following_id
+                .eq(actors::id)
+                .and(follows::follower_id.eq(actor.id))
+                .and(follows::role.ne(FOLLOW_PENDING))),
+        )
+        .order(follows::created_at.desc())
+        .limit(PAGE_SIZE)
+        .offset(PAGE_SIZE * (page - 1))
+        .load(db);
+    data.map(|v: Vec<(Actor, Follow)>| v.into_iter().map(|(a, _f)| a).collect())
+        .map_err(|_e| ActionError::NotFound)
+}
+
+pub fn actor_count_following(db: &PgConnection, actor: &Actor) -> ActionResult<i64> {
+    use diesel::dsl::count_star;
+    use schema::actors;
+    use schema::follows;
+    let data = actors::table
+        .inner_join(
+            follows::table.on(follows::following_id
+                .eq(actors::id)
+                .and(follows::follower_id.eq(actor.id))
+                .and(follows::role.ne(FOLLOW_PENDING))),
+        )
+        .select(count_star())
+        .first(db);
+    data.map_err(|_e| ActionError::NotFound)
+}
+
+pub fn actor_get_pending_followers(
+    db: &PgConnection,
+    actor: &Actor,
+    page: i64,
+) -> ActionResult<Vec<Actor>> {
+    use schema::actors;
+    use schema::follows;
+    let data = actors::table
+        .inner_join(
+            follows::table.on(follows::follower_id
+                .eq(actors::id)
+                .and(follows::following_id.eq(actor.id))
+                .and(follows::role.eq(FOLLOW_PENDING))),
+        )
+        .order(follows::created_at.asc())
+        .limit(PAGE_SIZE)
+        .offset(PAGE_SIZE * (page - 1))
+        .load(db);
+    data.map(|v: Vec<(Actor, Follow)>| v.into_iter().map(|(a, _f)| a).collect())
+        .map_err(|_e| ActionError::NotFound)
+}
+
+/// Approve a pending follow request, turning it into a follower.
+pub fn approve_follow(db: &PgConnection, follower: &Actor, following: &Actor) -> ActionResult<Follow> {
+    use schema::follows::dsl::*;
+    diesel::update(
+        follows.filter(
+            follower_id
+                .eq(follower.id)
+                .and(following_id.eq(following.id))
+                .and(role.eq(FOLLOW_PENDING)),
+        ),
+    )
+    .set((
+        role.eq(FOLLOW_FOLLOWER),
+        updated_at.eq(Some(Utc::now().naive_utc())),
+    ))
+    .get_result::<Follow>(db)
+    .map_err(ActionError::from)
+}
+
+/// Reject a pending follow request, and return the deleted follow.
+pub fn reject_follow(db: &PgConnection, follower: &Actor, following: &Actor) -> ActionResult<Follow> {
+    use schema::follows::dsl::*;
+    diesel::delete(
+        follows.filter(
+            follower_id
+                .eq(follower.id)
+                .and(following_id.eq(following.id))
+                .and(role.eq(FOLLOW_PENDING)),
+        ),
+    )
+    .get_result::<Follow>(db)
+    .map_err(ActionError::from)
+}
+
+/// Record a follow, which stays pending if the followed actor is locked.
+///
+/// Following again only updates the id of the Follow activity, so that a repeated Follow
+/// can be answered again.
 pub fn follow_actor_by_uri(
     db: &PgConnection,
     follower_uri: &str,
     following_uri: &str,
+    follow_activity_uri: Option<&str>,
 ) -> ActionResult<Follow> {
     let follower_actor = get_actor_by_uri(db, follower_uri)?;
     let following_actor = get_actor_by_uri(db, following_uri)?;
+    let role = if following_actor.is_locked {
+        FOLLOW_PENDING
+    } else {
+        FOLLOW_FOLLOWER
+    };
+    insert_follow(db, &follower_actor, &following_actor, role, follow_activity_uri)
+}
+
+/// Record a follow sent to a remote actor, which stays pending until it is accepted.
+pub fn request_follow(
+    db: &PgConnection,
+    follower: &Actor,
+    following: &Actor,
+    follow_activity_uri: &str,
+) -> ActionResult<Follow> {
+    insert_follow(db, follower, following, FOLLOW_PENDING, Some(follow_activity_uri))
+}
+
+fn insert_follow(
+    db: &PgConnection,
+    follower: &Actor,
+    following: &Actor,
+    role: &str,
+    follow_activity_uri: Option<&str>,
+) -> ActionResult<Follow> {
+    use schema::follows;
     let now = Utc::now().naive_utc();
     let new_follow = Follow {
-        follower_id: follower_actor.id,
-        following_id: following_actor.id,
+        follower_id: follower.id,
+        following_id: following.id,
         created_at: now,
         updated_at: Some(now),
-        role: if following_actor.is_locked {
-            String::from("pending")
-        } else {
-            String::from("follower")
-        },
+        role: String::from(role),
+        uri: follow_activity_uri.map(String::from),
     };
 
-    diesel::insert_into(schema::follows::table)
+    diesel::insert_into(follows::table)
         .values(&new_follow)
+        .on_conflict((follows::follower_id, follows::following_id))
+        .do_update()
+        .set((
+            follows::uri.eq(&new_follow.uri),
+            follows::updated_at.eq(&new_follow.updated_at),
+        ))
         .get_result::<Follow>(db)
         .map_err(|_e| ActionError::InsertError)
 }
 
+pub fn get_follow(db: &PgConnection, follower: &Actor, following: &Actor) -> ActionResult<Follow> {
+    use schema::follows::dsl::*;
+    follows
+        .filter(follower_id.eq(follower.id).and(following_id.eq(following.id)))
+        .first(db)
+        .map_err(ActionError::from)
+}
+
+/// Find a follow by the id of its Follow activity.
+pub fn get_follow_by_uri(db: &PgConnection, uri_in: &str) -> ActionResult<Follow> {
+    use schema::follows::dsl::*;
+    follows
+        .filter(uri.eq(uri_in))
+        .first(db)
+        .map_err(ActionError::from)
+}
+
+/// Stop following an actor; the moderators of a community keep their role when they unfollow it.
 pub fn unfollow_actor_by_uri(
     db: &PgConnection,
     follower_uri: &str,
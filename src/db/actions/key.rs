@@ -0,0 +1,139 @@
+use crate::apub;
+use crate::apub::models::is_same_origin;
+use crate::db::actions::actor::get_actor_by_id;
+use crate::db::models::{Actor, Key, NewKey};
+use crate::db::schema;
+use crate::errors::{ActionError, ActionResult};
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+pub fn get_key_by_uri(db: &PgConnection, uri_in: &str) -> ActionResult<Key> {
+    use schema::keys::dsl::*;
+    keys.filter(uri.eq(uri_in)).first(db).map_err(ActionError::from)
+}
+
+pub fn actor_get_keys(db: &PgConnection, actor: &Actor) -> ActionResult<Vec<Key>> {
+    use schema::keys::dsl::*;
+    keys.filter(actor_id.eq(actor.id))
+        .order(id.asc())
+        .load(db)
+        .map_err(ActionError::from)
+}
+
+/// Whether `actor` may hold the key `key_id`: it must be served by the host of the actor, and
+/// not be held by an actor of another host already.
+fn can_own_key(db: &PgConnection, actor: &Actor, key_id: &str) -> ActionResult<bool> {
+    if !is_same_origin(key_id, &actor.uri) {
+        return Ok(false);
+    }
+    match get_key_by_uri(db, key_id) {
+        Ok(key) if key.actor_id != actor.id => {
+            let holder = get_actor_by_id(db, key.actor_id)?;
+            Ok(is_same_origin(&holder.uri, &actor.uri))
+        }
+        Ok(_) | Err(ActionError::NotFound) => Ok(true),
+        Err(err) => Err(err),
+    }
+}
+
+/// Replace the keys of an actor with `public_keys`, as pairs of keyId and PEM.
+///
+/// A keyId held by another actor of the same host is taken over, since the actor listing it is
+/// its owner now; keys of other hosts are never assigned to the actor.
+pub fn set_actor_keys(db: &PgConnection, actor: &Actor, public_keys: &[(String, String)]) -> ActionResult<Vec<Key>> {
+    use schema::keys::dsl::*;
+    db.transaction::<Vec<Key>, ActionError, _>(|| {
+        let uris: Vec<&str> = public_keys.iter().map(|(key_id, _pem)| key_id.as_str()).collect();
+        diesel::delete(keys.filter(actor_id.eq(actor.id)).filter(uri.ne_all(uris)))
+            .execute(db)
+            .map_err(ActionError::from)?;
+        for (key_id, pem) in public_keys {
+            if !can_own_key(db, actor, key_id.as_str())? {
+                continue;
+            }
+            let new_key = NewKey {
+                uri: key_id.clone(),
+                actor_id: actor.id,
+                public_key_pem: pem.clone(),
+                private_key_pem: None,
+            };
+            diesel::insert_into(keys)
+                .values(&new_key)
+                .on_conflict(uri)
+                .do_update()
+                .set((actor_id.eq(new_key.actor_id), public_key_pem.eq(&new_key.public_key_pem)))
+                .execute(db)
+                .map_err(|_e| ActionError::InsertError)?;
+        }
+        actor_get_keys(db, actor)
+    })
+}
+
+/// Add a key pair to a local actor, besides its main key.
+pub fn insert_local_key(
+    db: &PgConnection,
+    actor: &Actor,
+    key_id: &str,
+    public_key_pem: &str,
+    private_key_pem: &str,
+) -> ActionResult<Key> {
+    let new_key = NewKey {
+        uri: String::from(key_id),
+        actor_id: actor.id,
+        public_key_pem: String::from(public_key_pem),
+        private_key_pem: Some(String::from(private_key_pem)),
+    };
+    diesel::insert_into(schema::keys::table)
+        .values(&new_key)
+        .get_result(db)
+        .map_err(|_e| ActionError::InsertError)
+}
+
+/// The Ed25519 key pair of a local actor; actors created before local actors got one have none
+/// until their keys are backfilled.
+pub fn get_local_ed25519_key(db: &PgConnection, actor: &Actor) -> ActionResult<Key> {
+    let key_id = apub::models::ed25519_key_id(actor.uri.as_str());
+    match get_key_by_uri(db, key_id.as_str())? {
+        key if key.actor_id == actor.id && key.private_key_pem.is_some() => Ok(key),
+        _key => Err(ActionError::NotFound),
+    }
+}
+
+/// The Ed25519 key pair of a local actor, generated now if the actor was created before local
+/// actors got one.
+pub fn get_or_create_local_ed25519_key(db: &PgConnection, actor: &Actor) -> ActionResult<Key> {
+    let key_id = apub::models::ed25519_key_id(actor.uri.as_str());
+    match get_key_by_uri(db, key_id.as_str()) {
+        Ok(key) if key.actor_id == actor.id && key.private_key_pem.is_some() => return Ok(key),
+        Ok(_key) => return Err(ActionError::NotFound),
+        Err(ActionError::NotFound) => (),
+        Err(err) => return Err(err),
+    }
+    let keypair = apub::ed25519::generate_key_pair_pem().ok_or(ActionError::InternalError)?;
+    insert_local_key(db, actor, key_id.as_str(), keypair.public.as_str(), keypair.private.as_str())
+}
+
+/// Give an Ed25519 key pair to every local actor created before they had one, and return the new
+/// keys.
+pub fn create_missing_local_ed25519_keys(db: &PgConnection) -> ActionResult<Vec<Key>> {
+    use schema::{actors, communities, users};
+    let local_actors = actors::table
+        .filter(
+            actors::id
+                .eq_any(users::table.select(users::actor_id))
+                .or(actors::id.eq_any(communities::table.select(communities::actor_id))),
+        )
+        .order(actors::id.asc())
+        .load::<Actor>(db)
+        .map_err(ActionError::from)?;
+    let mut created = vec![];
+    for actor in local_actors {
+        let key_id = apub::models::ed25519_key_id(actor.uri.as_str());
+        match get_key_by_uri(db, key_id.as_str()) {
+            Ok(_key) => (),
+            Err(ActionError::NotFound) => created.push(get_or_create_local_ed25519_key(db, &actor)?),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(created)
+}
@@ -0,0 +1,201 @@
+use crate::db::models::{Actor, CommunityBan, CommunityModerator, Post};
+use crate::db::schema;
+use crate::errors::{ActionError, ActionResult};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+/// Moderators of a community, the owner first.
+pub fn community_get_moderators(db: &PgConnection, community: &Actor) -> ActionResult<Vec<Actor>> {
+    use schema::actors;
+    use schema::communities;
+    use schema::community_moderators;
+    let mut moderators = actors::table
+        .inner_join(communities::table.on(communities::owner_id.eq(actors::id)))
+        .filter(communities::actor_id.eq(community.id))
+        .select(actors::all_columns)
+        .load::<Actor>(db)
+        .map_err(ActionError::from)?;
+    let appointed = actors::table
+        .inner_join(community_moderators::table.on(community_moderators::actor_id.eq(actors::id)))
+        .filter(community_moderators::community_id.eq(community.id))
+        .order(community_moderators::created_at.asc())
+        .select(actors::all_columns)
+        .load::<Actor>(db)
+        .map_err(ActionError::from)?;
+    moderators.extend(appointed);
+    Ok(moderators)
+}
+
+/// Whether `actor` created the local community `community`.
+pub fn is_owner(db: &PgConnection, community: &Actor, actor: &Actor) -> ActionResult<bool> {
+    use schema::communities::dsl::*;
+    communities
+        .filter(actor_id.eq(community.id))
+        .filter(owner_id.eq(actor.id))
+        .count()
+        .get_result::<i64>(db)
+        .map(|count| count > 0)
+        .map_err(ActionError::from)
+}
+
+pub fn is_moderator(db: &PgConnection, community: &Actor, actor: &Actor) -> ActionResult<bool> {
+    use schema::community_moderators::dsl::*;
+    if is_owner(db, community, actor)? {
+        return Ok(true);
+    }
+    community_moderators
+        .find((community.id, actor.id))
+        .count()
+        .get_result::<i64>(db)
+        .map(|count| count > 0)
+        .map_err(ActionError::from)
+}
+
+fn check_owner(db: &PgConnection, community: &Actor, actor: &Actor) -> ActionResult<()> {
+    if is_owner(db, community, actor)? {
+        Ok(())
+    } else {
+        Err(ActionError::Forbidden)
+    }
+}
+
+fn check_moderator(db: &PgConnection, community: &Actor, actor: &Actor) -> ActionResult<()> {
+    if is_moderator(db, community, actor)? {
+        Ok(())
+    } else {
+        Err(ActionError::Forbidden)
+    }
+}
+
+/// Appoint a moderator, on behalf of the owner of the community.
+pub fn add_moderator(db: &PgConnection, owner: &Actor, community: &Actor, actor: &Actor) -> ActionResult<CommunityModerator> {
+    use schema::community_moderators;
+    db.transaction::<CommunityModerator, ActionError, _>(|| {
+        check_owner(db, community, owner)?;
+        if is_owner(db, community, actor)? {
+            return Err(ActionError::InvalidForm);
+        }
+        let moderator = CommunityModerator {
+            community_id: community.id,
+            actor_id: actor.id,
+            created_at: Utc::now().naive_utc(),
+        };
+        diesel::insert_into(community_moderators::table)
+            .values(&moderator)
+            .on_conflict_do_nothing()
+            .execute(db)
+            .map_err(|_e| ActionError::InsertError)?;
+        community_moderators::table
+            .find((community.id, actor.id))
+            .first(db)
+            .map_err(ActionError::from)
+    })
+}
+
+/// Dismiss a moderator, on behalf of the owner of the community.
+pub fn remove_moderator(db: &PgConnection, owner: &Actor, community: &Actor, actor: &Actor) -> ActionResult<CommunityModerator> {
+    use schema::community_moderators::dsl::*;
+    db.transaction::<CommunityModerator, ActionError, _>(|| {
+        check_owner(db, community, owner)?;
+        diesel::delete(community_moderators.find((community.id, actor.id)))
+            .get_result::<CommunityModerator>(db)
+            .map_err(ActionError::from)
+    })
+}
+
+/// Remove a post from its community, or restore it.
+pub fn set_post_removed(db: &PgConnection, moderator: &Actor, post: &Post, removed: bool) -> ActionResult<Post> {
+    use schema::posts::dsl::*;
+    db.transaction::<Post, ActionError, _>(|| {
+        let community = crate::db::actions::actor::get_actor_by_id(db, post.community_id)?;
+        check_moderator(db, &community, moderator)?;
+        diesel::update(post)
+            .set(is_removed.eq(removed))
+            .get_result(db)
+            .map_err(ActionError::from)
+    })
+}
+
+/// Pin a thread to the top of its community, or unpin it.
+pub fn set_post_pinned(db: &PgConnection, moderator: &Actor, post: &Post, pinned: bool) -> ActionResult<Post> {
+    use schema::posts::dsl::*;
+    if !post.is_thread() {
+        return Err(ActionError::InvalidForm);
+    }
+    db.transaction::<Post, ActionError, _>(|| {
+        let community = crate::db::actions::actor::get_actor_by_id(db, post.community_id)?;
+        check_moderator(db, &community, moderator)?;
+        diesel::update(post)
+            .set(is_pinned.eq(pinned))
+            .get_result(db)
+            .map_err(ActionError::from)
+    })
+}
+
+/// Ban an actor from posting to a community, until `expires_at` or forever.
+///
+/// Moderators can't be banned; they have to be removed by the owner first.
+pub fn ban_actor(
+    db: &PgConnection,
+    moderator: &Actor,
+    community: &Actor,
+    actor: &Actor,
+    reason: &str,
+    expires_at: Option<chrono::NaiveDateTime>,
+) -> ActionResult<CommunityBan> {
+    use schema::community_bans;
+    db.transaction::<CommunityBan, ActionError, _>(|| {
+        check_moderator(db, community, moderator)?;
+        if is_moderator(db, community, actor)? {
+            return Err(ActionError::Forbidden);
+        }
+        let ban = CommunityBan {
+            community_id: community.id,
+            actor_id: actor.id,
+            moderator_id: moderator.id,
+            reason: String::from(reason),
+            created_at: Utc::now().naive_utc(),
+            expires_at,
+        };
+        diesel::insert_into(community_bans::table)
+            .values(&ban)
+            .on_conflict((community_bans::community_id, community_bans::actor_id))
+            .do_update()
+            .set((
+                community_bans::moderator_id.eq(ban.moderator_id),
+                community_bans::reason.eq(&ban.reason),
+                community_bans::created_at.eq(ban.created_at),
+                community_bans::expires_at.eq(ban.expires_at),
+            ))
+            .get_result::<CommunityBan>(db)
+            .map_err(|_e| ActionError::InsertError)
+    })
+}
+
+/// Lift a ban, and return it.
+pub fn unban_actor(db: &PgConnection, moderator: &Actor, community: &Actor, actor: &Actor) -> ActionResult<CommunityBan> {
+    use schema::community_bans::dsl::*;
+    db.transaction::<CommunityBan, ActionError, _>(|| {
+        check_moderator(db, community, moderator)?;
+        diesel::delete(
+            community_bans
+                .filter(community_id.eq(community.id))
+                .filter(actor_id.eq(actor.id)),
+        )
+        .get_result::<CommunityBan>(db)
+        .map_err(ActionError::from)
+    })
+}
+
+/// Whether an actor is currently banned from a community; expired bans don't count.
+pub fn is_banned(db: &PgConnection, community: &Actor, actor: &Actor) -> ActionResult<bool> {
+    use schema::community_bans::dsl::*;
+    community_bans
+        .filter(community_id.eq(community.id))
+        .filter(actor_id.eq(actor.id))
+        .first::<CommunityBan>(db)
+        .optional()
+        .map(|ban| matches!(ban, Some(ban) if ban.is_active()))
+        .map_err(ActionError::from)
+}
@@ -0,0 +1,184 @@
+use crate::db::actions::actor::get_actor_by_id;
+use crate::db::models::{Actor, NewPost, NewPostRevision, Post, PostRevision, PostView};
+use crate::db::schema;
+use crate::errors::{ActionError, ActionResult};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::PgConnection;
+use validator::Validate;
+
+pub const PAGE_SIZE: i64 = 20;
+
+/// Store a new thread, a top-level post of a community.
+pub fn insert_thread(db: &PgConnection, new_post: NewPost) -> ActionResult<Post> {
+    let new_post = NewPost {
+        in_reply_to_id: None,
+        thread_id: None,
+        ..new_post
+    };
+    insert_new_post(db, new_post)
+}
+
+/// Store a new comment replying to `parent`, which may be a thread or another comment.
+///
+/// The comment belongs to the thread and the community of its parent.
+pub fn insert_comment(db: &PgConnection, parent: &Post, new_post: NewPost) -> ActionResult<Post> {
+    let new_post = NewPost {
+        community_id: parent.community_id,
+        in_reply_to_id: Some(parent.id),
+        thread_id: Some(parent.thread_id.unwrap_or(parent.id)),
+        ..new_post
+    };
+    insert_new_post(db, new_post)
+}
+
+fn insert_new_post(db: &PgConnection, new_post: NewPost) -> ActionResult<Post> {
+    new_post.validate().map_err(|_e| ActionError::InvalidForm)?;
+    diesel::insert_into(schema::posts::table)
+        .values(&new_post)
+        .get_result::<Post>(db)
+        .map_err(|_e| ActionError::InsertError)
+}
+
+pub fn get_post_by_id(db: &PgConnection, id_in: i64) -> ActionResult<Post> {
+    use schema::posts::dsl::*;
+    posts.find(id_in).first(db).map_err(ActionError::from)
+}
+
+pub fn get_post_by_uri(db: &PgConnection, uri_in: &str) -> ActionResult<Post> {
+    use schema::posts::dsl::*;
+    posts
+        .filter(uri.eq(uri_in))
+        .first(db)
+        .map_err(ActionError::from)
+}
+
+/// Threads of a community which are not removed, pinned ones first and then newest first.
+pub fn community_get_threads(db: &PgConnection, community: &Actor, page: i64) -> ActionResult<Vec<Post>> {
+    use schema::posts::dsl::*;
+    posts
+        .filter(community_id.eq(community.id))
+        .filter(in_reply_to_id.is_null())
+        .filter(is_removed.eq(false))
+        .order((is_pinned.desc(), published.desc(), id.desc()))
+        .limit(PAGE_SIZE)
+        .offset(PAGE_SIZE * (page - 1))
+        .load(db)
+        .map_err(|_e| ActionError::NotFound)
+}
+
+pub fn community_count_threads(db: &PgConnection, community: &Actor) -> ActionResult<i64> {
+    use diesel::dsl::count_star;
+    use schema::posts::dsl::*;
+    posts
+        .filter(community_id.eq(community.id))
+        .filter(in_reply_to_id.is_null())
+        .filter(is_removed.eq(false))
+        .select(count_star())
+        .first(db)
+        .map_err(|_e| ActionError::NotFound)
+}
+
+/// All comments of a thread, oldest first.
+pub fn thread_get_comments(db: &PgConnection, thread: &Post) -> ActionResult<Vec<Post>> {
+    use schema::posts::dsl::*;
+    posts
+        .filter(thread_id.eq(thread.id))
+        .order((published.asc(), id.asc()))
+        .load(db)
+        .map_err(|_e| ActionError::NotFound)
+}
+
+/// Direct replies to a thread or a comment, oldest first.
+pub fn post_get_replies(db: &PgConnection, post: &Post) -> ActionResult<Vec<Post>> {
+    use schema::posts::dsl::*;
+    posts
+        .filter(in_reply_to_id.eq(post.id))
+        .order((published.asc(), id.asc()))
+        .load(db)
+        .map_err(|_e| ActionError::NotFound)
+}
+
+pub fn get_post_view(db: &PgConnection, post: Post) -> ActionResult<PostView> {
+    let author = get_actor_by_id(db, post.author_id)?;
+    let community = get_actor_by_id(db, post.community_id)?;
+    let in_reply_to = match post.in_reply_to_id {
+        Some(parent_id) => Some(get_post_by_id(db, parent_id)?),
+        None => None,
+    };
+    let reactions = super::reaction::post_count_reactions(db, &post)?;
+    Ok(PostView {
+        post,
+        author,
+        community,
+        in_reply_to,
+        reactions,
+    })
+}
+
+/// Replace a post with a tombstone, keeping the row so that replies stay in their thread.
+pub fn tombstone_post(db: &PgConnection, post: &Post) -> ActionResult<Post> {
+    use schema::posts::dsl::*;
+    diesel::update(post)
+        .set((
+            title.eq(None::<String>),
+            content.eq(""),
+            url.eq(None::<String>),
+            deleted_at.eq(Some(Utc::now().naive_utc())),
+        ))
+        .get_result(db)
+        .map_err(ActionError::from)
+}
+
+/// Replace every post by `author` with a tombstone, such as when the author was deleted.
+pub fn author_tombstone_posts(db: &PgConnection, author: &Actor) -> ActionResult<usize> {
+    use schema::posts::dsl::*;
+    diesel::update(posts.filter(author_id.eq(author.id)).filter(deleted_at.is_null()))
+        .set((
+            title.eq(None::<String>),
+            content.eq(""),
+            url.eq(None::<String>),
+            deleted_at.eq(Some(Utc::now().naive_utc())),
+        ))
+        .execute(db)
+        .map_err(ActionError::from)
+}
+
+/// Replace the contents of a post with those of an edited version, keeping the former version as
+/// a revision.
+pub fn revise_post(db: &PgConnection, post: &Post, new_post: &NewPost) -> ActionResult<Post> {
+    use schema::posts::dsl::*;
+    new_post.validate().map_err(|_e| ActionError::InvalidForm)?;
+    let revision = NewPostRevision {
+        post_id: post.id,
+        title: post.title.clone(),
+        content: post.content.clone(),
+        url: post.url.clone(),
+        published: post.updated_at.unwrap_or(post.published),
+    };
+    db.transaction::<Post, ActionError, _>(|| {
+        diesel::insert_into(schema::post_revisions::table)
+            .values(&revision)
+            .execute(db)
+            .map_err(|_e| ActionError::InsertError)?;
+        diesel::update(post)
+            .set((
+                title.eq(&new_post.title),
+                content.eq(&new_post.content),
+                url.eq(&new_post.url),
+                updated_at.eq(Some(new_post.updated_at.unwrap_or_else(|| Utc::now().naive_utc()))),
+            ))
+            .get_result(db)
+            .map_err(ActionError::from)
+    })
+}
+
+/// Former versions of a post, oldest first.
+pub fn post_get_revisions(db: &PgConnection, post: &Post) -> ActionResult<Vec<PostRevision>> {
+    use schema::post_revisions::dsl::*;
+    post_revisions
+        .filter(post_id.eq(post.id))
+        .order(id.asc())
+        .load(db)
+        .map_err(ActionError::from)
+}
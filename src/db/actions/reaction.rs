@@ -0,0 +1,107 @@
+use crate::db::models::{Actor, Post, Reaction, ReactionCount};
+use crate::db::schema;
+use crate::errors::{ActionError, ActionResult};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+/// Longest content of a reaction, which is a single emoji or a shortcode.
+pub const MAX_REACTION_LENGTH: usize = 128;
+
+/// Record the reaction of an actor to a post; reacting again with the same emoji only replaces
+/// the activity it was sent with.
+///
+/// Custom emojis with the same shortcode but different images are different reactions. The unique
+/// index on them is over an expression, which diesel can't name as a conflict target.
+pub fn add_reaction(
+    db: &PgConnection,
+    actor: &Actor,
+    post: &Post,
+    content: &str,
+    emoji_url: Option<&str>,
+    reaction_uri: Option<&str>,
+) -> ActionResult<Reaction> {
+    use diesel::sql_types::{BigInt, Nullable, Timestamp, Varchar};
+    if content.trim().is_empty() || content.chars().count() > MAX_REACTION_LENGTH {
+        return Err(ActionError::InvalidForm);
+    }
+    diesel::sql_query(
+        r#"INSERT INTO "reactions" ("post_id", "actor_id", "content", "emoji_url", "uri", "created_at")
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT ("post_id", "actor_id", "content", COALESCE("emoji_url", ''))
+        DO UPDATE SET "uri" = EXCLUDED."uri", "created_at" = EXCLUDED."created_at"
+        RETURNING *"#,
+    )
+    .bind::<BigInt, _>(post.id)
+    .bind::<BigInt, _>(actor.id)
+    .bind::<Varchar, _>(content)
+    .bind::<Nullable<Varchar>, _>(emoji_url)
+    .bind::<Nullable<Varchar>, _>(reaction_uri)
+    .bind::<Timestamp, _>(Utc::now().naive_utc())
+    .get_result::<Reaction>(db)
+    .map_err(|_e| ActionError::InsertError)
+}
+
+/// Withdraw the reaction of an actor sent with the activity `reaction_uri`, and return it.
+pub fn remove_reaction_by_uri(db: &PgConnection, actor: &Actor, reaction_uri: &str) -> ActionResult<Reaction> {
+    use schema::reactions::dsl::*;
+    diesel::delete(reactions.filter(actor_id.eq(actor.id)).filter(uri.eq(reaction_uri)))
+        .get_result(db)
+        .map_err(ActionError::from)
+}
+
+/// Withdraw the reaction of an actor to a post with an emoji, and return it.
+pub fn remove_reaction(
+    db: &PgConnection,
+    actor: &Actor,
+    post: &Post,
+    content_in: &str,
+    emoji_url_in: Option<&str>,
+) -> ActionResult<Reaction> {
+    use schema::reactions::dsl::*;
+    diesel::delete(
+        reactions
+            .filter(post_id.eq(post.id))
+            .filter(actor_id.eq(actor.id))
+            .filter(content.eq(content_in))
+            .filter(emoji_url.is_not_distinct_from(emoji_url_in)),
+    )
+    .get_result(db)
+    .map_err(ActionError::from)
+}
+
+/// Withdraw all the reactions of an actor, such as when it is deleted, and return them.
+pub fn actor_remove_reactions(db: &PgConnection, actor: &Actor) -> ActionResult<Vec<Reaction>> {
+    use schema::reactions::dsl::*;
+    diesel::delete(reactions.filter(actor_id.eq(actor.id)))
+        .get_results(db)
+        .map_err(ActionError::from)
+}
+
+/// Reactions to a post counted per emoji, in the order they were first used.
+///
+/// Custom emojis are told apart by their image, since servers may give the same shortcode to
+/// different ones.
+pub fn post_count_reactions(db: &PgConnection, post: &Post) -> ActionResult<Vec<ReactionCount>> {
+    use diesel::dsl::sql;
+    use diesel::sql_types::BigInt;
+    use schema::reactions::dsl::*;
+    // Diesel 1 can't select an aggregate along with columns, so the count is plain SQL.
+    reactions
+        .filter(post_id.eq(post.id))
+        .group_by((content, emoji_url))
+        .order(diesel::dsl::min(id).asc())
+        .select((content, emoji_url, sql::<BigInt>("count(*)")))
+        .load::<(String, Option<String>, i64)>(db)
+        .map(|counts| {
+            counts
+                .into_iter()
+                .map(|(reaction_content, reaction_emoji_url, count)| ReactionCount {
+                    content: reaction_content,
+                    emoji_url: reaction_emoji_url,
+                    count,
+                })
+                .collect()
+        })
+        .map_err(ActionError::from)
+}
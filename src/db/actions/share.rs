@@ -0,0 +1,69 @@
+use crate::db::models::{Actor, Post, Share};
+use crate::db::schema;
+use crate::errors::{ActionError, ActionResult};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+/// Add `delta` shares to the counter of a post.
+fn count_shares(db: &PgConnection, post_id: i64, delta: i32) -> ActionResult<()> {
+    use schema::posts::dsl::*;
+    diesel::update(posts.find(post_id))
+        .set(shares.eq(shares + delta))
+        .execute(db)
+        .map(|_count| ())
+        .map_err(ActionError::from)
+}
+
+/// Record a share of a post by an actor, and count it unless the actor already shared the post.
+///
+/// `share_uri` is the Announce which shared the post.
+pub fn add_share(db: &PgConnection, actor: &Actor, post: &Post, share_uri: Option<&str>) -> ActionResult<Share> {
+    use schema::shares;
+    let share = Share {
+        post_id: post.id,
+        actor_id: actor.id,
+        uri: share_uri.map(String::from),
+        created_at: Utc::now().naive_utc(),
+    };
+    db.transaction::<Share, ActionError, _>(|| {
+        let inserted = diesel::insert_into(shares::table)
+            .values(&share)
+            .on_conflict_do_nothing()
+            .execute(db)
+            .map_err(|_e| ActionError::InsertError)?;
+        if inserted > 0 {
+            count_shares(db, post.id, 1)?;
+        }
+        shares::table
+            .find((post.id, actor.id))
+            .first(db)
+            .map_err(ActionError::from)
+    })
+}
+
+/// Withdraw the share of a post by an actor, and return it.
+pub fn remove_share(db: &PgConnection, actor: &Actor, post: &Post) -> ActionResult<Share> {
+    use schema::shares::dsl::*;
+    db.transaction::<Share, ActionError, _>(|| {
+        let share = diesel::delete(shares.find((post.id, actor.id)))
+            .get_result::<Share>(db)
+            .map_err(ActionError::from)?;
+        count_shares(db, post.id, -1)?;
+        Ok(share)
+    })
+}
+
+/// Withdraw all the shares of an actor, such as when it is deleted, and return them.
+pub fn actor_remove_shares(db: &PgConnection, actor: &Actor) -> ActionResult<Vec<Share>> {
+    use schema::shares::dsl::*;
+    db.transaction::<Vec<Share>, ActionError, _>(|| {
+        let removed = diesel::delete(shares.filter(actor_id.eq(actor.id)))
+            .get_results::<Share>(db)
+            .map_err(ActionError::from)?;
+        for share in &removed {
+            count_shares(db, share.post_id, -1)?;
+        }
+        Ok(removed)
+    })
+}
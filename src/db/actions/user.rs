@@ -2,6 +2,7 @@ use diesel::prelude::*;
 use diesel::PgConnection;
 
 use crate::apub;
+use crate::db::actions::key::{insert_local_key, set_actor_keys};
 use crate::db::schema;
 
 use crate::db::models::actor::{Actor, ActorType, NewLocalActorBuilder};
@@ -28,6 +29,10 @@ pub fn create_user(
         Some(pair) => pair,
         None => return Err(ActionError::InternalError),
     };
+    let ed25519_keypair = match apub::ed25519::generate_key_pair_pem() {
+        Some(pair) => pair,
+        None => return Err(ActionError::InternalError),
+    };
 
     let new_actor = NewLocalActorBuilder {
         username,
@@ -61,7 +66,42 @@ pub fn create_user(
             .values(&new_user)
             .get_result::<User>(conn)
             .map_err(|_| ActionError::InsertError)?;
+        set_actor_keys(conn, &actor, &[(apub::models::main_key_id(actor.uri.as_str()), actor.public_key_pem.clone())])?;
+        insert_local_key(
+            conn,
+            &actor,
+            apub::models::ed25519_key_id(actor.uri.as_str()).as_str(),
+            ed25519_keypair.public.as_str(),
+            ed25519_keypair.private.as_str(),
+        )?;
 
         Ok(UserActor { actor, user })
     })
 }
+
+pub fn get_user_actor_by_actor_id(conn: &PgConnection, actor_id_in: i64) -> ActionResult<UserActor> {
+    use schema::actors;
+    use schema::users;
+    actors::table
+        .inner_join(users::table)
+        .filter(actors::id.eq(actor_id_in))
+        .first::<(Actor, User)>(conn)
+        .map(|(actor, user)| UserActor { actor, user })
+        .map_err(ActionError::from)
+}
+
+pub fn get_user_actor_by_username_domain(
+    conn: &PgConnection,
+    username_in: &str,
+    domain_in: &str,
+) -> ActionResult<UserActor> {
+    use schema::actors;
+    use schema::users;
+    actors::table
+        .inner_join(users::table)
+        .filter(actors::username.eq(username_in))
+        .filter(actors::domain.eq(domain_in))
+        .first::<(Actor, User)>(conn)
+        .map(|(actor, user)| UserActor { actor, user })
+        .map_err(ActionError::from)
+}
@@ -0,0 +1,97 @@
+use crate::db::models::{Actor, Post, Vote, VOTE_DOWN, VOTE_UP};
+use crate::db::schema;
+use crate::errors::{ActionError, ActionResult};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+pub fn get_vote(db: &PgConnection, actor: &Actor, post: &Post) -> ActionResult<Vote> {
+    use schema::votes::dsl::*;
+    votes
+        .find((post.id, actor.id))
+        .first(db)
+        .map_err(ActionError::from)
+}
+
+/// Add `delta` votes of the kind of `score` to the counters of a post.
+fn count_votes(db: &PgConnection, post_id: i64, score: i16, delta: i32) -> ActionResult<()> {
+    use schema::posts::dsl::*;
+    let query = diesel::update(posts.find(post_id));
+    if score > 0 {
+        query.set(upvotes.eq(upvotes + delta)).execute(db)
+    } else {
+        query.set(downvotes.eq(downvotes + delta)).execute(db)
+    }
+    .map(|_count| ())
+    .map_err(ActionError::from)
+}
+
+/// Record the vote of an actor on a post, replacing its former vote, and count it.
+///
+/// `vote_uri` is the Like or Dislike which sent the vote.
+pub fn set_vote(db: &PgConnection, actor: &Actor, post: &Post, score: i16, vote_uri: Option<&str>) -> ActionResult<Vote> {
+    use schema::votes;
+    if score != VOTE_UP && score != VOTE_DOWN {
+        return Err(ActionError::InvalidForm);
+    }
+    db.transaction::<Vote, ActionError, _>(|| {
+        // Votes of the same actor on the same post are counted one after the other, so that each
+        // sees the former.
+        schema::posts::table
+            .find(post.id)
+            .for_update()
+            .first::<Post>(db)
+            .map_err(ActionError::from)?;
+        match get_vote(db, actor, post) {
+            Ok(former) => count_votes(db, post.id, former.score, -1)?,
+            Err(ActionError::NotFound) => (),
+            Err(err) => return Err(err),
+        }
+        let vote = Vote {
+            post_id: post.id,
+            actor_id: actor.id,
+            score,
+            uri: vote_uri.map(String::from),
+            created_at: Utc::now().naive_utc(),
+        };
+        let vote = diesel::insert_into(votes::table)
+            .values(&vote)
+            .on_conflict((votes::post_id, votes::actor_id))
+            .do_update()
+            .set((
+                votes::score.eq(vote.score),
+                votes::uri.eq(&vote.uri),
+                votes::created_at.eq(vote.created_at),
+            ))
+            .get_result::<Vote>(db)
+            .map_err(|_e| ActionError::InsertError)?;
+        count_votes(db, post.id, vote.score, 1)?;
+        Ok(vote)
+    })
+}
+
+/// Withdraw the vote of an actor on a post, and return it.
+pub fn remove_vote(db: &PgConnection, actor: &Actor, post: &Post) -> ActionResult<Vote> {
+    use schema::votes::dsl::*;
+    db.transaction::<Vote, ActionError, _>(|| {
+        let vote = diesel::delete(votes.find((post.id, actor.id)))
+            .get_result::<Vote>(db)
+            .map_err(ActionError::from)?;
+        count_votes(db, post.id, vote.score, -1)?;
+        Ok(vote)
+    })
+}
+
+/// Withdraw all the votes of an actor, such as when it is deleted, and return them.
+pub fn actor_remove_votes(db: &PgConnection, actor: &Actor) -> ActionResult<Vec<Vote>> {
+    use schema::votes::dsl::*;
+    db.transaction::<Vec<Vote>, ActionError, _>(|| {
+        let removed = diesel::delete(votes.filter(actor_id.eq(actor.id)))
+            .get_results::<Vote>(db)
+            .map_err(ActionError::from)?;
+        for vote in &removed {
+            count_votes(db, vote.post_id, vote.score, -1)?;
+        }
+        Ok(removed)
+    })
+}
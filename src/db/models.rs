@@ -1,13 +1,35 @@
 pub mod actor;
 pub mod user;
 pub mod follow;
+pub mod delivery;
+pub mod activity;
+pub mod post;
+pub mod community;
+pub mod key;
+pub mod vote;
+pub mod reaction;
+pub mod share;
 
 pub use actor::*;
 pub use user::*;
 pub use follow::*;
+pub use delivery::*;
+pub use activity::*;
+pub use post::*;
+pub use community::*;
+pub use key::*;
+pub use vote::*;
+pub use reaction::*;
+pub use share::*;
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct UserActor {
     pub actor: Actor,
     pub user: User,
-}
\ No newline at end of file
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct CommunityActor {
+    pub actor: Actor,
+    pub community: Community,
+}
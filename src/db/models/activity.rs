@@ -0,0 +1,28 @@
+use crate::db::schema::activities;
+use chrono;
+
+/// An activity published by a local actor.
+#[derive(Clone, Identifiable, Queryable, Associations, PartialEq, Debug)]
+#[belongs_to(super::Actor)]
+#[table_name = "activities"]
+pub struct Activity {
+    pub id: i64,
+    pub uri: String,
+    pub actor_id: i64,
+    pub kind: String,
+    /// The activity as JSON.
+    pub body: String,
+    pub is_public: bool,
+    pub published: chrono::NaiveDateTime,
+}
+
+#[derive(Clone, Insertable, PartialEq, Debug)]
+#[table_name = "activities"]
+pub struct NewActivity {
+    pub uri: String,
+    pub actor_id: i64,
+    pub kind: String,
+    pub body: String,
+    pub is_public: bool,
+    pub published: chrono::NaiveDateTime,
+}
@@ -35,6 +35,10 @@ pub struct Actor {
     pub is_locked: bool,
     pub is_suspended: bool,
     pub is_silenced: bool,
+
+    pub shared_inbox_uri: Option<String>,
+    /// Set once the server of a remote actor announced its deletion.
+    pub deleted_at: Option<chrono::NaiveDateTime>,
 }
 
 #[derive(Clone, Insertable, PartialEq, Debug, Deserialize, Default, Validate)]
@@ -75,6 +79,9 @@ pub struct NewActor {
     pub is_locked: bool,
     pub is_suspended: bool,
     pub is_silenced: bool,
+
+    #[validate(url)]
+    pub shared_inbox_uri: Option<String>,
 }
 
 pub enum ActorType {
@@ -84,6 +91,17 @@ pub enum ActorType {
     Group,
 }
 
+impl Actor {
+    /// The inbox deliveries to this actor go to, shared with other actors of its server if possible.
+    pub fn delivery_inbox_uri(&self) -> &str {
+        self.shared_inbox_uri.as_deref().unwrap_or(self.inbox_uri.as_str())
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+}
+
 impl From<&Actor> for ActorType {
     fn from(actor: &Actor) -> ActorType {
         match actor.kind.as_str() {
@@ -127,6 +145,7 @@ impl NewLocalActorBuilder<'_> {
             outbox_uri: self.outbox_uri(),
             followers_uri: self.followers_uri(),
             following_uri: self.following_uri(),
+            shared_inbox_uri: Some(format!("https://{}/inbox", self.domain)),
             created_at: Some(now),
             updated_at: Some(now),
             public_key_pem: String::from(self.public_key_pem),
@@ -0,0 +1,48 @@
+use crate::db::schema::{communities, community_bans, community_moderators};
+use chrono;
+
+/// The local part of a community, whose public side is a Group actor.
+#[derive(Clone, Identifiable, Queryable, Insertable, Associations, PartialEq, Debug)]
+#[belongs_to(super::Actor)]
+#[table_name = "communities"]
+#[primary_key(actor_id)]
+pub struct Community {
+    pub actor_id: i64,
+    /// The local user who created the community.
+    pub owner_id: i64,
+    pub private_key_pem: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// An actor appointed by the owner of a community to moderate it.
+#[derive(Clone, Identifiable, Queryable, Insertable, PartialEq, Debug)]
+#[table_name = "community_moderators"]
+#[primary_key(community_id, actor_id)]
+pub struct CommunityModerator {
+    pub community_id: i64,
+    pub actor_id: i64,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// An actor banned from posting to a community.
+#[derive(Clone, Identifiable, Queryable, Insertable, PartialEq, Debug)]
+#[table_name = "community_bans"]
+#[primary_key(community_id, actor_id)]
+pub struct CommunityBan {
+    pub community_id: i64,
+    pub actor_id: i64,
+    pub moderator_id: i64,
+    pub reason: String,
+    pub created_at: chrono::NaiveDateTime,
+    /// The ban is permanent if not set.
+    pub expires_at: Option<chrono::NaiveDateTime>,
+}
+
+impl CommunityBan {
+    pub fn is_active(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => expires_at > chrono::Utc::now().naive_utc(),
+            None => true,
+        }
+    }
+}
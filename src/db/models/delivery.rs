@@ -0,0 +1,51 @@
+use crate::db::schema::{deliveries, host_signature_styles};
+use chrono;
+
+pub const DELIVERY_PENDING: &str = "pending";
+pub const DELIVERY_DELIVERED: &str = "delivered";
+pub const DELIVERY_FAILED: &str = "failed";
+
+/// Requests are signed with RFC 9421 HTTP Message Signatures.
+pub const SIGNATURE_STYLE_RFC9421: &str = "rfc9421";
+/// Requests are signed with draft-cavage-http-signatures.
+pub const SIGNATURE_STYLE_CAVAGE: &str = "cavage";
+
+#[derive(Clone, Identifiable, Queryable, Associations, PartialEq, Debug)]
+#[belongs_to(super::Actor)]
+#[table_name = "deliveries"]
+pub struct Delivery {
+    pub id: i64,
+    /// The local actor whose key signs the request.
+    pub actor_id: i64,
+    pub inbox_uri: String,
+    pub body: String,
+
+    pub status: String,
+    pub attempts: i32,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+
+    pub next_attempt_at: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Clone, Insertable, PartialEq, Debug)]
+#[table_name = "deliveries"]
+pub struct NewDelivery {
+    pub actor_id: i64,
+    pub inbox_uri: String,
+    pub body: String,
+    pub next_attempt_at: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// How a remote host accepted a signed request, to sign the next ones the same way.
+#[derive(Clone, Identifiable, Queryable, Insertable, PartialEq, Debug)]
+#[table_name = "host_signature_styles"]
+#[primary_key(host)]
+pub struct HostSignatureStyle {
+    pub host: String,
+    pub style: String,
+    pub updated_at: chrono::NaiveDateTime,
+}
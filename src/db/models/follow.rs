@@ -1,6 +1,9 @@
 use crate::db::schema::follows;
 use chrono;
 
+pub const FOLLOW_PENDING: &str = "pending";
+pub const FOLLOW_FOLLOWER: &str = "follower";
+
 #[derive(Clone, Identifiable, Queryable, Insertable, Associations, PartialEq, Debug)]
 #[primary_key(follower_id, following_id)]
 #[table_name = "follows"]
@@ -12,4 +15,6 @@ pub struct Follow {
     pub updated_at: Option<chrono::NaiveDateTime>,
     
     pub role: String,
+    /// id of the Follow activity.
+    pub uri: Option<String>,
 }
\ No newline at end of file
@@ -0,0 +1,25 @@
+use crate::db::schema::keys;
+use chrono;
+
+/// A public key an actor signs requests with, by its keyId.
+#[derive(Clone, Identifiable, Queryable, Associations, PartialEq, Debug)]
+#[belongs_to(super::Actor)]
+#[table_name = "keys"]
+pub struct Key {
+    pub id: i64,
+    pub uri: String,
+    pub actor_id: i64,
+    pub public_key_pem: String,
+    pub created_at: chrono::NaiveDateTime,
+    /// Only known for the keys of local actors besides their main key.
+    pub private_key_pem: Option<String>,
+}
+
+#[derive(Clone, Insertable, PartialEq, Debug)]
+#[table_name = "keys"]
+pub struct NewKey {
+    pub uri: String,
+    pub actor_id: i64,
+    pub public_key_pem: String,
+    pub private_key_pem: Option<String>,
+}
@@ -0,0 +1,107 @@
+use crate::db::schema::{post_revisions, posts};
+use chrono;
+
+use validator::Validate;
+
+/// A thread (top-level post of a community) or a comment replying to a thread or comment.
+#[derive(Clone, Identifiable, Queryable, PartialEq, Debug)]
+#[table_name = "posts"]
+pub struct Post {
+    pub id: i64,
+    pub uri: String,
+    pub url: Option<String>,
+    pub kind: String,
+
+    pub author_id: i64,
+    pub community_id: i64,
+    pub in_reply_to_id: Option<i64>,
+    /// The thread a comment belongs to, None for threads.
+    pub thread_id: Option<i64>,
+
+    pub title: Option<String>,
+    pub content: String,
+
+    pub published: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+    /// Deleted posts are kept as tombstones, so that their replies stay in place.
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    /// Removed by a moderator of the community.
+    pub is_removed: bool,
+    /// Pinned to the top of the community by a moderator.
+    pub is_pinned: bool,
+    pub upvotes: i32,
+    pub downvotes: i32,
+    /// Announces of the post by people.
+    pub shares: i32,
+}
+
+impl Post {
+    pub fn is_thread(&self) -> bool {
+        self.in_reply_to_id.is_none()
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    pub fn score(&self) -> i32 {
+        self.upvotes - self.downvotes
+    }
+}
+
+/// A post along with the actors and the post it refers to.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PostView {
+    pub post: Post,
+    pub author: super::Actor,
+    pub community: super::Actor,
+    pub in_reply_to: Option<Post>,
+    pub reactions: Vec<super::ReactionCount>,
+}
+
+#[derive(Clone, Insertable, PartialEq, Debug, Validate)]
+#[table_name = "posts"]
+pub struct NewPost {
+    #[validate(url)]
+    pub uri: String,
+    #[validate(url)]
+    pub url: Option<String>,
+    pub kind: String,
+
+    pub author_id: i64,
+    pub community_id: i64,
+    pub in_reply_to_id: Option<i64>,
+    pub thread_id: Option<i64>,
+
+    #[validate(non_control_character)]
+    pub title: Option<String>,
+    pub content: String,
+
+    pub published: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
+/// A former version of a post, replaced when its author edited it.
+#[derive(Clone, Identifiable, Queryable, PartialEq, Debug)]
+#[table_name = "post_revisions"]
+pub struct PostRevision {
+    pub id: i64,
+    pub post_id: i64,
+    pub title: Option<String>,
+    pub content: String,
+    pub url: Option<String>,
+    /// When this version was published.
+    pub published: chrono::NaiveDateTime,
+    /// When this version was replaced.
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Clone, Insertable, PartialEq, Debug)]
+#[table_name = "post_revisions"]
+pub struct NewPostRevision {
+    pub post_id: i64,
+    pub title: Option<String>,
+    pub content: String,
+    pub url: Option<String>,
+    pub published: chrono::NaiveDateTime,
+}
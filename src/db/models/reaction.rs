@@ -0,0 +1,26 @@
+use crate::db::schema::reactions;
+use chrono;
+
+/// An emoji reaction of an actor to a post, sent as an EmojiReact or a Like with content.
+#[derive(Clone, Identifiable, Queryable, QueryableByName, PartialEq, Debug)]
+#[table_name = "reactions"]
+pub struct Reaction {
+    pub id: i64,
+    pub post_id: i64,
+    pub actor_id: i64,
+    /// A Unicode emoji, or the `:shortcode:` of a custom one.
+    pub content: String,
+    /// The image of a custom emoji.
+    pub emoji_url: Option<String>,
+    /// The EmojiReact or Like activity.
+    pub uri: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// How many actors reacted to a post with the same emoji.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReactionCount {
+    pub content: String,
+    pub emoji_url: Option<String>,
+    pub count: i64,
+}
@@ -0,0 +1,14 @@
+use crate::db::schema::shares;
+use chrono;
+
+/// An Announce of a post by an actor other than its community, such as a boost.
+#[derive(Clone, Identifiable, Queryable, Insertable, PartialEq, Debug)]
+#[table_name = "shares"]
+#[primary_key(post_id, actor_id)]
+pub struct Share {
+    pub post_id: i64,
+    pub actor_id: i64,
+    /// The Announce activity.
+    pub uri: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+}
@@ -0,0 +1,30 @@
+use crate::db::schema::votes;
+use chrono;
+
+pub const VOTE_UP: i16 = 1;
+pub const VOTE_DOWN: i16 = -1;
+
+/// An upvote or a downvote of a post by an actor, sent as a Like or a Dislike.
+#[derive(Clone, Identifiable, Queryable, Insertable, PartialEq, Debug)]
+#[table_name = "votes"]
+#[primary_key(post_id, actor_id)]
+pub struct Vote {
+    pub post_id: i64,
+    pub actor_id: i64,
+    /// `VOTE_UP` or `VOTE_DOWN`.
+    pub score: i16,
+    /// The Like or Dislike activity.
+    pub uri: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl Vote {
+    /// The type of the activity sending the vote.
+    pub fn activity_kind(&self) -> &'static str {
+        if self.score > 0 {
+            "Like"
+        } else {
+            "Dislike"
+        }
+    }
+}
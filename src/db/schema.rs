@@ -1,3 +1,15 @@
+table! {
+    activities (id) {
+        id -> Int8,
+        uri -> Varchar,
+        actor_id -> Int8,
+        kind -> Varchar,
+        body -> Text,
+        is_public -> Bool,
+        published -> Timestamp,
+    }
+}
+
 table! {
     actors (id) {
         id -> Int8,
@@ -20,6 +32,52 @@ table! {
         is_locked -> Bool,
         is_suspended -> Bool,
         is_silenced -> Bool,
+        shared_inbox_uri -> Nullable<Varchar>,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    communities (actor_id) {
+        actor_id -> Int8,
+        owner_id -> Int8,
+        private_key_pem -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    community_bans (community_id, actor_id) {
+        community_id -> Int8,
+        actor_id -> Int8,
+        moderator_id -> Int8,
+        reason -> Text,
+        created_at -> Timestamp,
+        expires_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    community_moderators (community_id, actor_id) {
+        community_id -> Int8,
+        actor_id -> Int8,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    deliveries (id) {
+        id -> Int8,
+        actor_id -> Int8,
+        inbox_uri -> Varchar,
+        body -> Text,
+        status -> Varchar,
+        attempts -> Int4,
+        last_status_code -> Nullable<Int4>,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Timestamp,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
     }
 }
 
@@ -30,6 +88,89 @@ table! {
         created_at -> Timestamp,
         updated_at -> Nullable<Timestamp>,
         role -> Varchar,
+        uri -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    host_signature_styles (host) {
+        host -> Varchar,
+        style -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    keys (id) {
+        id -> Int8,
+        uri -> Varchar,
+        actor_id -> Int8,
+        public_key_pem -> Text,
+        created_at -> Timestamp,
+        private_key_pem -> Nullable<Text>,
+    }
+}
+
+table! {
+    post_revisions (id) {
+        id -> Int8,
+        post_id -> Int8,
+        title -> Nullable<Varchar>,
+        content -> Text,
+        url -> Nullable<Varchar>,
+        published -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    posts (id) {
+        id -> Int8,
+        uri -> Varchar,
+        url -> Nullable<Varchar>,
+        kind -> Varchar,
+        author_id -> Int8,
+        community_id -> Int8,
+        in_reply_to_id -> Nullable<Int8>,
+        thread_id -> Nullable<Int8>,
+        title -> Nullable<Varchar>,
+        content -> Text,
+        published -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        deleted_at -> Nullable<Timestamp>,
+        is_removed -> Bool,
+        is_pinned -> Bool,
+        upvotes -> Int4,
+        downvotes -> Int4,
+        shares -> Int4,
+    }
+}
+
+table! {
+    processed_activities (uri) {
+        uri -> Varchar,
+        processed_at -> Timestamp,
+    }
+}
+
+table! {
+    reactions (id) {
+        id -> Int8,
+        post_id -> Int8,
+        actor_id -> Int8,
+        content -> Varchar,
+        emoji_url -> Nullable<Varchar>,
+        uri -> Nullable<Varchar>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    shares (post_id, actor_id) {
+        post_id -> Int8,
+        actor_id -> Int8,
+        uri -> Nullable<Varchar>,
+        created_at -> Timestamp,
     }
 }
 
@@ -45,10 +186,44 @@ table! {
     }
 }
 
+table! {
+    votes (post_id, actor_id) {
+        post_id -> Int8,
+        actor_id -> Int8,
+        score -> Int2,
+        uri -> Nullable<Varchar>,
+        created_at -> Timestamp,
+    }
+}
+
+joinable!(activities -> actors (actor_id));
+joinable!(communities -> actors (actor_id));
+joinable!(deliveries -> actors (actor_id));
+joinable!(keys -> actors (actor_id));
+joinable!(post_revisions -> posts (post_id));
+joinable!(reactions -> actors (actor_id));
+joinable!(reactions -> posts (post_id));
+joinable!(shares -> actors (actor_id));
+joinable!(shares -> posts (post_id));
 joinable!(users -> actors (actor_id));
+joinable!(votes -> actors (actor_id));
+joinable!(votes -> posts (post_id));
 
 allow_tables_to_appear_in_same_query!(
+    activities,
     actors,
+    communities,
+    community_bans,
+    community_moderators,
+    deliveries,
     follows,
+    host_signature_styles,
+    keys,
+    post_revisions,
+    posts,
+    processed_activities,
+    reactions,
+    shares,
     users,
+    votes,
 );
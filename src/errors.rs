@@ -23,6 +23,12 @@ pub enum ActionError {
 
     #[error("not authenticated")]
     NotAuthenticated,
+
+    #[error("delivery error")]
+    DeliveryError,
+
+    #[error("forbidden")]
+    Forbidden,
 }
 
 impl warp::reply::Reply for ActionError {
@@ -30,6 +36,8 @@ impl warp::reply::Reply for ActionError {
         let code = match &self {
             ActionError::NotFound => warp::http::StatusCode::NOT_FOUND,
             ActionError::InvalidForm => warp::http::StatusCode::UNPROCESSABLE_ENTITY,
+            ActionError::NotAuthenticated => warp::http::StatusCode::UNAUTHORIZED,
+            ActionError::Forbidden => warp::http::StatusCode::FORBIDDEN,
             _ => warp::http::StatusCode::INTERNAL_SERVER_ERROR,
         };
         warp::reply::with_status(warp::reply::json(&self), code).into_response()
@@ -25,7 +25,8 @@
 
 //! HTTP Signature handling utility.
 //!
-//! More details in the `Signature` struct
+//! More details in the `Signature` struct, for draft-cavage-http-signatures, and in the
+//! `MessageSignature` struct, for RFC 9421 HTTP Message Signatures.
 #![warn(missing_docs)]
 
 use warp::http;
@@ -53,6 +54,14 @@ pub enum ParseError {
     /// Signature field was not valid Base64
     #[error("Failed to parse signature bytes")]
     Base64(base64::DecodeError),
+
+    /// A structured field of RFC 9421 was malformed
+    #[error("Malformed structured field")]
+    InvalidStructure,
+
+    /// A component identifier had parameters, which are not supported
+    #[error("Unsupported component parameters")]
+    UnsupportedComponent,
 }
 
 /// Errors that may be produced when creating a signature
@@ -65,6 +74,10 @@ pub enum SignError<T: std::fmt::Debug> {
     /// An error was returned from the provided `sign` function.
     #[error("Failed in user sign call")]
     User(T),
+
+    /// The target URI of the request to sign could not be parsed.
+    #[error("Invalid target URI")]
+    InvalidTargetUri,
 }
 
 /// Errors that may be produced when verifying a signature
@@ -455,3 +468,328 @@ impl Signature {
         verify(&body, &self.signature).map_err(VerifyError::User)
     }
 }
+
+/// Split the members of a structured field list or dictionary, ignoring commas in strings and
+/// inner lists.
+fn split_members(src: &str) -> Vec<&str> {
+    let mut members = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut depth = 0;
+    let mut start = 0;
+    for (idx, c) in src.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                members.push(src[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    members.push(src[start..].trim());
+    members.into_iter().filter(|member| !member.is_empty()).collect()
+}
+
+/// Parse a structured field string at the start of `src`, returning it and the rest of `src`.
+fn parse_sf_string(src: &str) -> Result<(String, &str), ParseError> {
+    if !src.starts_with('"') {
+        return Err(ParseError::InvalidStructure);
+    }
+    let mut value = String::new();
+    let mut escaped = false;
+    for (idx, c) in src.char_indices().skip(1) {
+        if escaped {
+            value.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            return Ok((value, &src[(idx + 1)..]));
+        } else {
+            value.push(c);
+        }
+    }
+    Err(ParseError::InvalidStructure)
+}
+
+/// Serialize a structured field string.
+fn serialize_sf_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A parsed or generated RFC 9421 HTTP Message Signature.
+#[derive(Clone)]
+pub struct MessageSignature {
+    /// Label of the signature in the `Signature-Input` and `Signature` dictionaries.
+    pub label: String,
+    /// Covered component identifiers, such as `@method` or `content-digest`.
+    pub components: Vec<String>,
+    /// `created` parameter.
+    pub created: Option<u64>,
+    /// `expires` parameter.
+    pub expires: Option<u64>,
+    /// `keyid` parameter.
+    pub key_id: Option<String>,
+    /// `alg` parameter.
+    pub algorithm: Option<String>,
+    /// The signature parameters as serialized in `Signature-Input`, which are signed as is.
+    pub params: String,
+    /// signature data.
+    pub signature: Vec<u8>,
+}
+
+impl MessageSignature {
+    /// Construct a signature.
+    ///
+    /// `@method` and `@target-uri` will be covered, as well as all headers in `headers`, and the
+    /// `created` and `expires` parameters are set based on `lifetime_secs`.
+    ///
+    /// The passed `sign` will be called with the signature base.
+    pub fn create<E: std::fmt::Debug>(
+        key_id: &str,
+        algorithm: &str,
+        request_method: &http::method::Method,
+        target_uri: &str,
+        lifetime_secs: u64,
+        headers: &http::header::HeaderMap,
+        sign: impl FnOnce(Vec<u8>) -> Result<Vec<u8>, E>,
+    ) -> Result<Self, SignError<E>> {
+        use std::fmt::Write;
+
+        let created = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Timestamp is wildly unrealistic (before epoch)")
+            .as_secs();
+        let expires = created + lifetime_secs;
+
+        let components: Vec<String> = vec![String::from("@method"), String::from("@target-uri")]
+            .into_iter()
+            .chain(headers.keys().map(|name| String::from(name.as_str())))
+            .collect();
+
+        let mut params = String::from("(");
+        for (idx, component) in components.iter().enumerate() {
+            if idx != 0 {
+                params.push(' ');
+            }
+            params.push_str(serialize_sf_string(component).as_str());
+        }
+        write!(
+            params,
+            ");created={};expires={};keyid={};alg={}",
+            created,
+            expires,
+            serialize_sf_string(key_id),
+            serialize_sf_string(algorithm),
+        )
+        .unwrap();
+
+        let mut signature = Self {
+            label: String::from("sig1"),
+            components,
+            created: Some(created),
+            expires: Some(expires),
+            key_id: Some(String::from(key_id)),
+            algorithm: Some(String::from(algorithm)),
+            params,
+            signature: Vec::new(),
+        };
+        let base = signature
+            .signature_base(request_method, target_uri, headers)
+            .ok_or(SignError::InvalidTargetUri)?;
+        signature.signature = sign(base).map_err(SignError::User)?;
+        Ok(signature)
+    }
+
+    /// Parse the `Signature-Input` and `Signature` headers, returning every signature found in
+    /// both, in the order of `Signature-Input`.
+    pub fn parse(
+        signature_input: &http::header::HeaderValue,
+        signature: &http::header::HeaderValue,
+    ) -> Result<Vec<Self>, ParseError> {
+        let signature_input = signature_input.to_str().map_err(|_| ParseError::InvalidCharacters)?;
+        let signature = signature.to_str().map_err(|_| ParseError::InvalidCharacters)?;
+
+        let mut signatures = std::collections::HashMap::new();
+        for member in split_members(signature) {
+            let eqidx = member.find('=').ok_or(ParseError::MissingEquals)?;
+            let value = member[(eqidx + 1)..].trim();
+            if !(value.len() >= 2 && value.starts_with(':') && value.ends_with(':')) {
+                return Err(ParseError::InvalidStructure);
+            }
+            let bytes = base64::decode(&value[1..(value.len() - 1)]).map_err(ParseError::Base64)?;
+            signatures.insert(member[..eqidx].trim(), bytes);
+        }
+
+        let mut result = Vec::new();
+        for member in split_members(signature_input) {
+            let eqidx = member.find('=').ok_or(ParseError::MissingEquals)?;
+            let label = member[..eqidx].trim();
+            let params = member[(eqidx + 1)..].trim();
+            let bytes = match signatures.remove(label) {
+                Some(bytes) => bytes,
+                None => continue,
+            };
+            result.push(Self::parse_params(label, params, bytes)?);
+        }
+        if result.is_empty() {
+            return Err(ParseError::MissingSignature);
+        }
+        Ok(result)
+    }
+
+    fn parse_params(label: &str, params: &str, signature: Vec<u8>) -> Result<Self, ParseError> {
+        let mut rest = params.strip_prefix('(').ok_or(ParseError::InvalidStructure)?;
+        let mut components = Vec::new();
+        loop {
+            rest = rest.trim_start_matches(' ');
+            if let Some(after) = rest.strip_prefix(')') {
+                rest = after;
+                break;
+            }
+            let (component, after) = parse_sf_string(rest)?;
+            if after.starts_with(';') {
+                return Err(ParseError::UnsupportedComponent);
+            }
+            if !(after.starts_with(' ') || after.starts_with(')')) {
+                return Err(ParseError::InvalidStructure);
+            }
+            components.push(component);
+            rest = after;
+        }
+
+        let mut created = None;
+        let mut expires = None;
+        let mut key_id = None;
+        let mut algorithm = None;
+        while let Some(after) = rest.strip_prefix(';') {
+            let eqidx = after.find('=').ok_or(ParseError::MissingEquals)?;
+            let key = after[..eqidx].trim();
+            let value_src = &after[(eqidx + 1)..];
+            let (value, after) = if value_src.starts_with('"') {
+                parse_sf_string(value_src)?
+            } else {
+                let end = value_src.find(';').unwrap_or(value_src.len());
+                (String::from(&value_src[..end]), &value_src[end..])
+            };
+            match key {
+                "created" => created = Some(value.parse().map_err(ParseError::Number)?),
+                "expires" => expires = Some(value.parse().map_err(ParseError::Number)?),
+                "keyid" => key_id = Some(value),
+                "alg" => algorithm = Some(value),
+                _ => {}
+            }
+            rest = after;
+        }
+        if !rest.trim().is_empty() {
+            return Err(ParseError::InvalidStructure);
+        }
+
+        Ok(Self {
+            label: String::from(label),
+            components,
+            created,
+            expires,
+            key_id,
+            algorithm,
+            params: String::from(params),
+            signature,
+        })
+    }
+
+    /// Create the `Signature-Input` and `Signature` header values for the signature.
+    pub fn to_headers(&self) -> (http::header::HeaderValue, http::header::HeaderValue) {
+        let signature_input = format!("{}={}", self.label, self.params);
+        let signature = format!("{}=:{}:", self.label, base64::encode(&self.signature));
+        (
+            http::header::HeaderValue::from_str(signature_input.as_str()).unwrap(),
+            http::header::HeaderValue::from_str(signature.as_str()).unwrap(),
+        )
+    }
+
+    /// Whether the signature covers a component, such as `@method` or `content-digest`.
+    pub fn covers(&self, component: &str) -> bool {
+        self.components.iter().any(|covered| covered.as_str() == component)
+    }
+
+    /// The signature base of a request, or None if a covered component is missing from it or not
+    /// supported.
+    pub fn signature_base(
+        &self,
+        request_method: &http::method::Method,
+        target_uri: &str,
+        headers: &http::header::HeaderMap,
+    ) -> Option<Vec<u8>> {
+        let uri = target_uri.parse::<http::Uri>().ok()?;
+
+        let mut base = String::new();
+        for component in &self.components {
+            let value = match component.as_str() {
+                "@method" => String::from(request_method.as_str()),
+                "@target-uri" => String::from(target_uri),
+                "@authority" => uri.authority()?.as_str().to_lowercase(),
+                "@scheme" => uri.scheme_str()?.to_lowercase(),
+                "@request-target" => String::from(uri.path_and_query()?.as_str()),
+                "@path" => String::from(uri.path()),
+                "@query" => format!("?{}", uri.query().unwrap_or("")),
+                name if name.starts_with('@') => return None,
+                name => {
+                    let values: Vec<&str> = headers
+                        .get_all(name)
+                        .iter()
+                        .map(|value| value.to_str().map(str::trim))
+                        .collect::<Result<_, _>>()
+                        .ok()?;
+                    if values.is_empty() {
+                        return None;
+                    }
+                    values.join(", ")
+                }
+            };
+            base.push_str(format!("{}: {}\n", serialize_sf_string(component), value).as_str());
+        }
+        base.push_str(format!("\"@signature-params\": {}", self.params).as_str());
+        Some(base.into_bytes())
+    }
+
+    /// Verify the signature for a given request method, target URI and HeaderMap.
+    ///
+    /// The passed `verify` function will be called with (base, signature) where base is the
+    /// signature base that should match the signature.
+    pub fn verify<E: std::fmt::Debug>(
+        &self,
+        request_method: &http::method::Method,
+        target_uri: &str,
+        headers: &http::header::HeaderMap,
+        verify: impl FnOnce(&[u8], &[u8]) -> Result<bool, E>,
+    ) -> Result<bool, VerifyError<E>> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .expect("Timestamp is wildly inaccurate")
+            .as_secs();
+
+        if let Some(expires) = self.expires {
+            if expires < now {
+                return Ok(false);
+            }
+        }
+
+        match self.signature_base(request_method, target_uri, headers) {
+            Some(base) => verify(&base, &self.signature).map_err(VerifyError::User),
+            None => Ok(false),
+        }
+    }
+}
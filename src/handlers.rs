@@ -1,2 +1,3 @@
+pub mod api;
 pub mod apub;
 pub mod webfinger;
\ No newline at end of file
@@ -0,0 +1,26 @@
+pub mod auth;
+pub mod follows;
+pub mod moderation;
+pub mod votes;
+
+use serde::Deserialize;
+
+/// Request body naming an actor by its URI.
+#[derive(Deserialize)]
+pub struct ActorUriForm {
+    pub uri: String,
+}
+
+/// Request body naming a post by its URI.
+#[derive(Deserialize)]
+pub struct PostUriForm {
+    pub uri: String,
+}
+
+/// Request body of a ban; `expires_at` is an RFC 3339 date, and the ban is permanent without it.
+#[derive(Deserialize)]
+pub struct BanForm {
+    pub uri: String,
+    pub reason: Option<String>,
+    pub expires_at: Option<String>,
+}
@@ -0,0 +1,41 @@
+use crate::db::actions;
+use crate::db::models::UserActor;
+use crate::errors::{ActionError, ActionResult};
+use crate::state::AppState;
+use bcrypt;
+use std::sync::Arc;
+use tokio;
+
+/// Split the credentials of a `Basic` Authorization header into username and password.
+pub fn parse_basic_authorization(authorization: Option<&str>) -> Option<(String, String)> {
+    let encoded = authorization?.strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(base64::decode(encoded.trim()).ok()?).ok()?;
+    let mut credentials = decoded.splitn(2, ':');
+    let username = String::from(credentials.next()?);
+    let password = String::from(credentials.next()?);
+    Some((username, password))
+}
+
+/// Authenticate a local user of `domain` with HTTP Basic authentication.
+pub async fn authenticate_user(
+    app_state: Arc<AppState>,
+    domain: String,
+    authorization: Option<String>,
+) -> ActionResult<UserActor> {
+    let (username, password) =
+        parse_basic_authorization(authorization.as_deref()).ok_or(ActionError::NotAuthenticated)?;
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+
+    tokio::task::spawn_blocking(move || {
+        let user_actor = actions::user::get_user_actor_by_username_domain(&conn, username.as_str(), domain.as_str())
+            .map_err(|_e| ActionError::NotAuthenticated)?;
+        let password_hash = user_actor.user.password_hash.as_ref().ok_or(ActionError::NotAuthenticated)?;
+        if !user_actor.actor.is_suspended && bcrypt::verify(password, password_hash).unwrap_or(false) {
+            Ok(user_actor)
+        } else {
+            Err(ActionError::NotAuthenticated)
+        }
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError))
+}
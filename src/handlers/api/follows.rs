@@ -0,0 +1,164 @@
+use crate::apub;
+use crate::apub::models::PagedCollection;
+use crate::db::actions;
+use crate::db::models::Actor;
+use crate::errors::{ActionError, ActionResult};
+use crate::handlers::api::auth::authenticate_user;
+use crate::handlers::api::ActorUriForm;
+use crate::state::AppState;
+
+use diesel::Connection;
+use tokio;
+use warp;
+use warp::Reply;
+use std::sync::Arc;
+
+/// List the pending follow requests of the authenticated user.
+pub async fn get_follow_requests(
+    app_state: Arc<AppState>,
+    domain: String,
+    authorization: Option<String>,
+    paged_collection: PagedCollection,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = async {
+        let user_actor = authenticate_user(Arc::clone(&app_state), domain, authorization).await?;
+        let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+        let page_number = paged_collection.page_number().max(1);
+        tokio::task::spawn_blocking(move || {
+            actions::follow::actor_get_pending_followers(&conn, &user_actor.actor, page_number)
+        })
+        .await
+        .unwrap_or(Err(ActionError::InternalError))
+    }
+    .await;
+
+    match result {
+        Ok(actors) => Ok(warp::reply::json(&actors).into_response()),
+        Err(err) => Ok(err.into_response()),
+    }
+}
+
+/// Approve a pending follow request, and send Accept to the follower.
+pub async fn authorize_follow_request(
+    app_state: Arc<AppState>,
+    domain: String,
+    authorization: Option<String>,
+    form: ActorUriForm,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = resolve_follow_request(app_state, domain, authorization, form, true).await;
+
+    match result {
+        Ok(actor) => Ok(warp::reply::json(&actor).into_response()),
+        Err(err) => Ok(err.into_response()),
+    }
+}
+
+/// Reject a pending follow request, and send Reject to the follower.
+pub async fn reject_follow_request(
+    app_state: Arc<AppState>,
+    domain: String,
+    authorization: Option<String>,
+    form: ActorUriForm,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = resolve_follow_request(app_state, domain, authorization, form, false).await;
+
+    match result {
+        Ok(actor) => Ok(warp::reply::json(&actor).into_response()),
+        Err(err) => Ok(err.into_response()),
+    }
+}
+
+async fn resolve_follow_request(
+    app_state: Arc<AppState>,
+    domain: String,
+    authorization: Option<String>,
+    form: ActorUriForm,
+    approve: bool,
+) -> ActionResult<Actor> {
+    let user_actor = authenticate_user(Arc::clone(&app_state), domain, authorization).await?;
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+
+    tokio::task::spawn_blocking(move || {
+        let follower = actions::actor::get_actor_by_uri(&conn, form.uri.as_str())?;
+        let following = user_actor.actor;
+        conn.transaction::<Actor, ActionError, _>(|| {
+            if approve {
+                let follow = actions::follow::approve_follow(&conn, &follower, &following)?;
+                apub::actions::follow::send_accept_follow(&conn, &follower, &following, &follow)?;
+            } else {
+                let follow = actions::follow::reject_follow(&conn, &follower, &following)?;
+                apub::actions::follow::send_reject_follow(&conn, &follower, &following, &follow)?;
+            }
+            Ok(follower)
+        })
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError))
+}
+
+/// Follow an actor, sending Follow when it lives on another server.
+pub async fn post_follow(
+    app_state: Arc<AppState>,
+    domain: String,
+    authorization: Option<String>,
+    form: ActorUriForm,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = async {
+        let user_actor = authenticate_user(Arc::clone(&app_state), domain, authorization).await?;
+        let following = apub::actions::get_or_fetch_actor_by_uri(&app_state.db, form.uri.as_str()).await?;
+        if following.id == user_actor.actor.id {
+            return Err(ActionError::InvalidForm);
+        }
+        let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+        let app_state_move = Arc::clone(&app_state);
+
+        tokio::task::spawn_blocking(move || {
+            if app_state_move.local_domains.contains(&following.domain) {
+                actions::follow::follow_actor_by_uri(&conn, user_actor.actor.uri.as_str(), following.uri.as_str(), None)?;
+            } else {
+                apub::actions::follow::send_follow(&conn, &user_actor.actor, &following)?;
+            }
+            Ok(following)
+        })
+        .await
+        .unwrap_or(Err(ActionError::InternalError))
+    }
+    .await;
+
+    match result {
+        Ok(actor) => Ok(warp::reply::json(&actor).into_response()),
+        Err(err) => Ok(err.into_response()),
+    }
+}
+
+/// Stop following an actor, sending Undo when it lives on another server.
+pub async fn post_undo_follow(
+    app_state: Arc<AppState>,
+    domain: String,
+    authorization: Option<String>,
+    form: ActorUriForm,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = async {
+        let user_actor = authenticate_user(Arc::clone(&app_state), domain, authorization).await?;
+        let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+        let app_state_move = Arc::clone(&app_state);
+
+        tokio::task::spawn_blocking(move || {
+            let following = actions::actor::get_actor_by_uri(&conn, form.uri.as_str())?;
+            if app_state_move.local_domains.contains(&following.domain) {
+                actions::follow::unfollow_actor_by_uri(&conn, user_actor.actor.uri.as_str(), following.uri.as_str())?;
+            } else {
+                apub::actions::follow::send_undo_follow(&conn, &user_actor.actor, &following)?;
+            }
+            Ok(following)
+        })
+        .await
+        .unwrap_or(Err(ActionError::InternalError))
+    }
+    .await;
+
+    match result {
+        Ok(actor) => Ok(warp::reply::json(&actor).into_response()),
+        Err(err) => Ok(err.into_response()),
+    }
+}
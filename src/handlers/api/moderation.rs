@@ -0,0 +1,175 @@
+use crate::apub;
+use crate::db::actions;
+use crate::db::models::Actor;
+use crate::errors::{ActionError, ActionResult};
+use crate::handlers::api::auth::authenticate_user;
+use crate::handlers::api::{ActorUriForm, BanForm, PostUriForm};
+use crate::state::AppState;
+
+use tokio;
+use warp;
+use warp::Reply;
+use std::sync::Arc;
+
+/// Appoint a moderator of a community owned by the authenticated user.
+pub async fn post_add_moderator(
+    app_state: Arc<AppState>,
+    domain: String,
+    name: String,
+    authorization: Option<String>,
+    form: ActorUriForm,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = resolve_moderator(app_state, domain, name, authorization, form, true).await;
+
+    match result {
+        Ok(actor) => Ok(warp::reply::json(&actor).into_response()),
+        Err(err) => Ok(err.into_response()),
+    }
+}
+
+/// Dismiss a moderator of a community owned by the authenticated user.
+pub async fn post_remove_moderator(
+    app_state: Arc<AppState>,
+    domain: String,
+    name: String,
+    authorization: Option<String>,
+    form: ActorUriForm,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = resolve_moderator(app_state, domain, name, authorization, form, false).await;
+
+    match result {
+        Ok(actor) => Ok(warp::reply::json(&actor).into_response()),
+        Err(err) => Ok(err.into_response()),
+    }
+}
+
+async fn resolve_moderator(
+    app_state: Arc<AppState>,
+    domain: String,
+    name: String,
+    authorization: Option<String>,
+    form: ActorUriForm,
+    add: bool,
+) -> ActionResult<Actor> {
+    let user_actor = authenticate_user(Arc::clone(&app_state), domain.clone(), authorization).await?;
+    let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+
+    tokio::task::spawn_blocking(move || {
+        let community = actions::community::get_community_by_name_domain(&conn, name.as_str(), domain.as_str())?;
+        let actor = actions::actor::get_actor_by_uri(&conn, form.uri.as_str())?;
+        if add {
+            actions::moderation::add_moderator(&conn, &user_actor.actor, &community, &actor)?;
+        } else {
+            actions::moderation::remove_moderator(&conn, &user_actor.actor, &community, &actor)?;
+        }
+        Ok(actor)
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError))
+}
+
+/// Ban an actor from a community moderated by the authenticated user.
+pub async fn post_ban(
+    app_state: Arc<AppState>,
+    domain: String,
+    name: String,
+    authorization: Option<String>,
+    form: BanForm,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = async {
+        let expires_at = match &form.expires_at {
+            Some(expires_at) => Some(apub::models::parse_datetime(expires_at.as_str()).ok_or(ActionError::InvalidForm)?),
+            None => None,
+        };
+        let user_actor = authenticate_user(Arc::clone(&app_state), domain.clone(), authorization).await?;
+        let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+
+        tokio::task::spawn_blocking(move || {
+            let community = actions::community::get_community_by_name_domain(&conn, name.as_str(), domain.as_str())?;
+            let actor = actions::actor::get_actor_by_uri(&conn, form.uri.as_str())?;
+            let reason = form.reason.unwrap_or_default();
+            actions::moderation::ban_actor(&conn, &user_actor.actor, &community, &actor, reason.as_str(), expires_at)?;
+            Ok(actor)
+        })
+        .await
+        .unwrap_or(Err(ActionError::InternalError))
+    }
+    .await;
+
+    match result {
+        Ok(actor) => Ok(warp::reply::json(&actor).into_response()),
+        Err(err) => Ok(err.into_response()),
+    }
+}
+
+/// Lift a ban from a community moderated by the authenticated user.
+pub async fn post_undo_ban(
+    app_state: Arc<AppState>,
+    domain: String,
+    name: String,
+    authorization: Option<String>,
+    form: ActorUriForm,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = async {
+        let user_actor = authenticate_user(Arc::clone(&app_state), domain.clone(), authorization).await?;
+        let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+
+        tokio::task::spawn_blocking(move || {
+            let community = actions::community::get_community_by_name_domain(&conn, name.as_str(), domain.as_str())?;
+            let actor = actions::actor::get_actor_by_uri(&conn, form.uri.as_str())?;
+            actions::moderation::unban_actor(&conn, &user_actor.actor, &community, &actor)?;
+            Ok(actor)
+        })
+        .await
+        .unwrap_or(Err(ActionError::InternalError))
+    }
+    .await;
+
+    match result {
+        Ok(actor) => Ok(warp::reply::json(&actor).into_response()),
+        Err(err) => Ok(err.into_response()),
+    }
+}
+
+/// What a moderator does to a post.
+#[derive(Clone, Copy)]
+pub enum PostModeration {
+    Remove,
+    Restore,
+    Pin,
+    Unpin,
+}
+
+/// Remove, restore, pin or unpin a post in a community moderated by the authenticated user.
+pub async fn post_moderate_post(
+    app_state: Arc<AppState>,
+    domain: String,
+    moderation: PostModeration,
+    authorization: Option<String>,
+    form: PostUriForm,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = async {
+        let user_actor = authenticate_user(Arc::clone(&app_state), domain, authorization).await?;
+        let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+
+        tokio::task::spawn_blocking(move || {
+            let post = actions::post::get_post_by_uri(&conn, form.uri.as_str())?;
+            let moderator = &user_actor.actor;
+            let post = match moderation {
+                PostModeration::Remove => actions::moderation::set_post_removed(&conn, moderator, &post, true)?,
+                PostModeration::Restore => actions::moderation::set_post_removed(&conn, moderator, &post, false)?,
+                PostModeration::Pin => actions::moderation::set_post_pinned(&conn, moderator, &post, true)?,
+                PostModeration::Unpin => actions::moderation::set_post_pinned(&conn, moderator, &post, false)?,
+            };
+            actions::post::get_post_view(&conn, post)
+        })
+        .await
+        .unwrap_or(Err(ActionError::InternalError))
+    }
+    .await;
+
+    match result {
+        Ok(post_view) => Ok(warp::reply::json(&apub::models::Object::from(&post_view)).into_response()),
+        Err(err) => Ok(err.into_response()),
+    }
+}
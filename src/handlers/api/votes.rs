@@ -0,0 +1,59 @@
+use crate::apub;
+use crate::db::actions;
+use crate::db::models::{VOTE_DOWN, VOTE_UP};
+use crate::errors::ActionError;
+use crate::handlers::api::auth::authenticate_user;
+use crate::handlers::api::PostUriForm;
+use crate::state::AppState;
+
+use tokio;
+use warp;
+use warp::Reply;
+use std::sync::Arc;
+
+/// What a user does to the score of a post.
+#[derive(Clone, Copy)]
+pub enum VoteAction {
+    Up,
+    Down,
+    Undo,
+}
+
+/// Upvote, downvote or stop voting on a post, on behalf of the authenticated user.
+pub async fn post_vote(
+    app_state: Arc<AppState>,
+    domain: String,
+    action: VoteAction,
+    authorization: Option<String>,
+    form: PostUriForm,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = async {
+        let user_actor = authenticate_user(Arc::clone(&app_state), domain, authorization).await?;
+        let conn = app_state.db.get().map_err(|_e| ActionError::InternalError)?;
+        let app_state_move = Arc::clone(&app_state);
+
+        tokio::task::spawn_blocking(move || {
+            let post = actions::post::get_post_by_uri(&conn, form.uri.as_str())?;
+            if post.is_deleted() || post.is_removed {
+                return Err(ActionError::NotFound);
+            }
+            let voter = &user_actor.actor;
+            let local_domains = &app_state_move.local_domains;
+            match action {
+                VoteAction::Up => apub::actions::votes::send_vote(&conn, local_domains, voter, &post, VOTE_UP)?,
+                VoteAction::Down => apub::actions::votes::send_vote(&conn, local_domains, voter, &post, VOTE_DOWN)?,
+                VoteAction::Undo => apub::actions::votes::send_undo_vote(&conn, local_domains, voter, &post)?,
+            };
+            let post = actions::post::get_post_by_id(&conn, post.id)?;
+            actions::post::get_post_view(&conn, post)
+        })
+        .await
+        .unwrap_or(Err(ActionError::InternalError))
+    }
+    .await;
+
+    match result {
+        Ok(post_view) => Ok(warp::reply::json(&apub::models::Object::from(&post_view)).into_response()),
+        Err(err) => Ok(err.into_response()),
+    }
+}
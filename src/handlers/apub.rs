@@ -1,6 +1,7 @@
 pub mod actors;
 pub mod inbox;
 pub mod auth;
+pub mod objects;
 
 use warp;
 use warp::Filter;
@@ -10,6 +11,7 @@ use crate::errors::ActionError;
 use std::sync::Arc;
 use serde::de::DeserializeOwned;
 use bytes::{Bytes, Buf};
+use warp::http::header::HeaderMap;
 
 pub async fn set_domain(
     app_state: Arc<AppState>,
@@ -31,11 +33,15 @@ pub fn map_content_type_ap<T: warp::reply::Reply>(reply: T) -> warp::reply::With
     warp::reply::with_header(reply, "Content-Type", "application/activity+json")
 }
 
+/// Read a JSON body, after checking it against its `Digest` or `Content-Digest` header.
 pub fn activity_json<T: DeserializeOwned + Send>() -> impl Filter<Extract = (T,), Error = Rejection> + Copy {
-    warp::filters::body::bytes()
-    .and_then(|buf: Bytes| async move {
+    warp::header::headers_cloned()
+    .and(warp::filters::body::bytes())
+    .and_then(|headers: HeaderMap, buf: Bytes| async move {
         let mut buf = buf;
-        serde_json::from_slice(&buf.copy_to_bytes(buf.remaining())).map_err(|e| {
+        let body = buf.copy_to_bytes(buf.remaining());
+        auth::verify_digest(&headers, &body).map_err(warp::reject::custom)?;
+        serde_json::from_slice(&body).map_err(|e| {
             eprintln!("invalid form: {}", e);
             warp::reject::custom(ActionError::InvalidForm)
         })
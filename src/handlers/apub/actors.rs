@@ -11,16 +11,45 @@ use warp;
 use warp::Reply;
 use std::sync::Arc;
 use serde_json::json;
+use diesel::PgConnection;
+
+/// The path actors are served under, which also decides what kinds of actors it resolves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActorPath {
+    /// `/users/<name>`: people, services and applications.
+    Users,
+    /// `/communities/<name>`: groups only.
+    Communities,
+}
+
+impl ActorPath {
+    pub fn slug(&self) -> &'static str {
+        match self {
+            ActorPath::Users => "users",
+            ActorPath::Communities => "communities",
+        }
+    }
+
+    pub fn get_actor(&self, conn: &PgConnection, username: &str, domain: &str) -> errors::ActionResult<db::models::Actor> {
+        match self {
+            ActorPath::Users => actions::actor::get_person_by_username_domain(conn, username, domain),
+            ActorPath::Communities => actions::community::get_community_by_name_domain(conn, username, domain),
+        }
+    }
+}
 
 pub async fn get_user(
     app_state: Arc<AppState>,
     domain: String,
+    actor_path: ActorPath,
     username: String
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let conn = app_state.db.get().map_err(|_e| warp::reject())?;
 
     let result = tokio::task::spawn_blocking(move || {
-        actions::actor::get_actor_by_username_domain(&conn, username.as_str(), domain.as_str())
+        let actor = actor_path.get_actor(&conn, username.as_str(), domain.as_str())?;
+        let keys = actions::key::actor_get_keys(&conn, &actor)?;
+        Ok((actor, keys))
     })
     .await
     .or(Err(errors::ActionError::InternalError))
@@ -30,8 +59,8 @@ pub async fn get_user(
                 errors::ActionError::NotFound => e,
                 _ => errors::ActionError::InternalError,
             }
-        }).map(|actor| {
-            warp::reply::json(&apub::models::Actor::from(&actor))
+        }).map(|(actor, keys)| {
+            warp::reply::json(&apub::models::Actor::from(&actor).with_keys(&keys))
         })
     });
 
@@ -44,36 +73,87 @@ pub async fn get_user(
 
 
 pub async fn get_user_outbox(
-    _app_state: Arc<AppState>,
+    app_state: Arc<AppState>,
     domain: String,
-    username: String
+    actor_path: ActorPath,
+    username: String,
+    paged_collection: apub::models::PagedCollection
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let outbox = apub::models::Outbox {
-        context: apub::serializers::get_context(),
-        kind: String::from("OrderedCollection"),
-        id: format!("https://{}/users/{}/inbox", domain, username),
-        total_items: 0,
-        ordered_items: vec![],
+    let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+
+    let page_number = paged_collection.page_number();
+
+    let (actor, total_items, activities) = tokio::task::spawn_blocking(move || {
+        let actor = actor_path.get_actor(&conn, username.as_str(), domain.as_str())?;
+        let total_items = actions::activity::actor_count_outbox(&conn, &actor)?;
+        let activities = if page_number > 0 {
+            actions::activity::actor_get_outbox(&conn, &actor, page_number)?
+        } else {
+            vec![]
+        };
+        Ok((actor, total_items, activities))
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError))
+    .map_err(|err| warp::reject::custom(err))?;
+
+    if !paged_collection.is_paged() {
+        return Ok(warp::reply::json(&json!({
+            "@context": apub::serializers::get_context(),
+            "type": "OrderedCollection",
+            "id": actor.outbox_uri,
+            "totalItems": total_items,
+            "first": format!("{}?page=1", actor.outbox_uri)
+        })));
+    }
+
+    let ordered_items = activities.iter().filter_map(|activity| {
+        serde_json::from_str::<apub::models::Activity>(activity.body.as_str())
+            .ok()
+            .map(|activity| apub::models::Activity { context: None, ..activity })
+    }).collect::<Vec<apub::models::Activity>>();
+
+    let next = if paged_collection.has_next(total_items, db::actions::activity::PAGE_SIZE) {
+        Some(format!("{}?page={}", actor.outbox_uri, paged_collection.next_page_number()))
+    } else {
+        None
+    };
+    let prev = if paged_collection.has_prev() {
+        Some(format!("{}?page={}", actor.outbox_uri, paged_collection.prev_page_number()))
+    } else {
+        None
     };
-    Ok(warp::reply::json(&outbox))
+
+    Ok(warp::reply::json(&json!({
+        "@context": apub::serializers::get_context(),
+        "type": "OrderedCollectionPage",
+        "id": format!("{}?page={}", actor.outbox_uri, paged_collection.page_number()),
+        "partOf": actor.outbox_uri,
+        "next": next,
+        "prev": prev,
+        "totalItems": total_items,
+        "orderedItems": ordered_items,
+    })))
 }
 
 pub async fn get_user_followers(
     app_state: Arc<AppState>,
     domain: String,
+    actor_path: ActorPath,
     username: String,
     paged_collection: apub::models::PagedCollection
 ) -> Result<impl warp::Reply, warp::Rejection> {
     if paged_collection.is_paged() {
-        get_user_followers_paged(app_state, domain, username, paged_collection).await
+        get_user_followers_paged(app_state, domain, actor_path, username, paged_collection).await
     } else {
-        get_user_followers_not_paged(app_state, domain, username, paged_collection).await
+        get_user_followers_not_paged(app_state, domain, actor_path, username, paged_collection).await
     }
 }
 
 async fn get_user_followers_not_paged(
     app_state: Arc<AppState>,
     domain: String,
+    actor_path: ActorPath,
     username: String,
     _paged_collection: apub::models::PagedCollection
 ) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
@@ -83,7 +163,7 @@ async fn get_user_followers_not_paged(
     let domain_move = domain.clone();
 
     let total_items = tokio::task::spawn_blocking(move || {
-        let actor = actions::actor::get_actor_by_username_domain(&conn, username_move.as_str(), domain_move.as_str())?;
+        let actor = actor_path.get_actor(&conn, username_move.as_str(), domain_move.as_str())?;
         let total_items = actions::follow::actor_count_followers(&conn, &actor)?;
         Ok(total_items)
     })
@@ -94,15 +174,16 @@ async fn get_user_followers_not_paged(
     Ok(Box::new(warp::reply::json(&json!({
         "@context": apub::serializers::get_context(),
         "type": "OrderedCollection",
-        "id": format!("https://{}/users/{}/followers", domain, username),
+        "id": format!("https://{}/{}/{}/followers", domain, actor_path.slug(), username),
         "totalItems": total_items,
-        "first": format!("https://{}/users/{}/followers?page=1", domain, username)
+        "first": format!("https://{}/{}/{}/followers?page=1", domain, actor_path.slug(), username)
     }))))
 }
 
 async fn get_user_followers_paged(
     app_state: Arc<AppState>,
     domain: String,
+    actor_path: ActorPath,
     username: String,
     paged_collection: apub::models::PagedCollection
 ) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
@@ -113,7 +194,7 @@ async fn get_user_followers_paged(
     let page_number = paged_collection.page_number();
 
     let (total_items, actor_id_vec) = tokio::task::spawn_blocking(move || {
-        let actor = actions::actor::get_actor_by_username_domain(&conn, username_move.as_str(), domain_move.as_str())?;
+        let actor = actor_path.get_actor(&conn, username_move.as_str(), domain_move.as_str())?;
         let followers = actions::follow::actor_get_followers(&conn, &actor, page_number)?;
         let total_items = actions::follow::actor_count_followers(&conn, &actor)?;
         Ok((total_items, followers))
@@ -130,12 +211,12 @@ async fn get_user_followers_paged(
     })?;
 
     let next = if paged_collection.has_next(total_items, db::actions::follow::PAGE_SIZE) {
-        Some(format!("https://{}/users/{}/followers?page={}", domain, username, paged_collection.next_page_number()))
+        Some(format!("https://{}/{}/{}/followers?page={}", domain, actor_path.slug(), username, paged_collection.next_page_number()))
     } else {
         None
     };
     let prev = if paged_collection.has_prev() {
-        Some(format!("https://{}/users/{}/followers?page={}", domain, username, paged_collection.prev_page_number()))
+        Some(format!("https://{}/{}/{}/followers?page={}", domain, actor_path.slug(), username, paged_collection.prev_page_number()))
     } else {
         None
     };
@@ -143,10 +224,130 @@ async fn get_user_followers_paged(
     Ok(Box::new(warp::reply::json(&json!({
         "@context": apub::serializers::get_context(),
         "type": "OrderedCollectionPage",
-        "id": format!("https://{}/users/{}/followers?page={}", domain, username, paged_collection.page_number()),
+        "id": format!("https://{}/{}/{}/followers?page={}", domain, actor_path.slug(), username, paged_collection.page_number()),
         "next": next,
         "prev": prev,
         "totalItems": total_items,
         "orderedItems": actor_id_vec,
     }))))
-}
\ No newline at end of file
+}
+
+pub async fn get_user_following(
+    app_state: Arc<AppState>,
+    domain: String,
+    username: String,
+    paged_collection: apub::models::PagedCollection
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if paged_collection.is_paged() {
+        get_user_following_paged(app_state, domain, username, paged_collection).await
+    } else {
+        get_user_following_not_paged(app_state, domain, username, paged_collection).await
+    }
+}
+
+async fn get_user_following_not_paged(
+    app_state: Arc<AppState>,
+    domain: String,
+    username: String,
+    _paged_collection: apub::models::PagedCollection
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+
+    let username_move = username.clone();
+    let domain_move = domain.clone();
+
+    let total_items = tokio::task::spawn_blocking(move || {
+        let actor = actions::actor::get_person_by_username_domain(&conn, username_move.as_str(), domain_move.as_str())?;
+        let total_items = actions::follow::actor_count_following(&conn, &actor)?;
+        Ok(total_items)
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError))
+    .map_err(|err| warp::reject::custom(err))?;
+
+    Ok(Box::new(warp::reply::json(&json!({
+        "@context": apub::serializers::get_context(),
+        "type": "OrderedCollection",
+        "id": format!("https://{}/users/{}/following", domain, username),
+        "totalItems": total_items,
+        "first": format!("https://{}/users/{}/following?page=1", domain, username)
+    }))))
+}
+
+async fn get_user_following_paged(
+    app_state: Arc<AppState>,
+    domain: String,
+    username: String,
+    paged_collection: apub::models::PagedCollection
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+
+    let username_move = username.clone();
+    let domain_move = domain.clone();
+    let page_number = paged_collection.page_number();
+
+    let (total_items, actor_id_vec) = tokio::task::spawn_blocking(move || {
+        let actor = actions::actor::get_person_by_username_domain(&conn, username_move.as_str(), domain_move.as_str())?;
+        let following = actions::follow::actor_get_following(&conn, &actor, page_number)?;
+        let total_items = actions::follow::actor_count_following(&conn, &actor)?;
+        Ok((total_items, following))
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError))
+    .map_err(|err| warp::reject::custom(err))
+    .map(|(total_items, actor_vec)| {
+        let actor_id_vec = actor_vec.iter().map(|actor| {
+            actor.uri.clone()
+        }).collect::<Vec<String>>();
+
+        (total_items, actor_id_vec)
+    })?;
+
+    let next = if paged_collection.has_next(total_items, db::actions::follow::PAGE_SIZE) {
+        Some(format!("https://{}/users/{}/following?page={}", domain, username, paged_collection.next_page_number()))
+    } else {
+        None
+    };
+    let prev = if paged_collection.has_prev() {
+        Some(format!("https://{}/users/{}/following?page={}", domain, username, paged_collection.prev_page_number()))
+    } else {
+        None
+    };
+
+    Ok(Box::new(warp::reply::json(&json!({
+        "@context": apub::serializers::get_context(),
+        "type": "OrderedCollectionPage",
+        "id": format!("https://{}/users/{}/following?page={}", domain, username, paged_collection.page_number()),
+        "next": next,
+        "prev": prev,
+        "totalItems": total_items,
+        "orderedItems": actor_id_vec,
+    }))))
+}
+
+pub async fn get_community_moderators(
+    app_state: Arc<AppState>,
+    domain: String,
+    name: String
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+
+    let (actor, moderators) = tokio::task::spawn_blocking(move || {
+        let actor = actions::community::get_community_by_name_domain(&conn, name.as_str(), domain.as_str())?;
+        let moderators = actions::moderation::community_get_moderators(&conn, &actor)?;
+        Ok((actor, moderators))
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError))
+    .map_err(warp::reject::custom)?;
+
+    let ordered_items = moderators.iter().map(|actor| actor.uri.clone()).collect::<Vec<String>>();
+
+    Ok(warp::reply::json(&json!({
+        "@context": apub::serializers::get_context(),
+        "type": "OrderedCollection",
+        "id": apub::models::moderators_uri(actor.uri.as_str()),
+        "totalItems": ordered_items.len(),
+        "orderedItems": ordered_items,
+    })))
+}
@@ -5,6 +5,7 @@ use crate::errors;
 use crate::hancock;
 use crate::state::AppState;
 use crate::apub::models::Activity;
+use chrono::{DateTime, Duration, Utc};
 use log;
 use openssl;
 use std::sync::Arc;
@@ -25,6 +26,240 @@ pub fn do_verify(
     Ok(verifier.verify(sig)?)
 }
 
+/// Digest of `body` with an algorithm named in `Digest` (RFC 3230) or `Content-Digest`
+/// (RFC 9530), or None if the algorithm is not supported.
+fn body_digest(algorithm: &str, body: &[u8]) -> Option<Vec<u8>> {
+    match algorithm.to_ascii_lowercase().as_str() {
+        "sha-256" => Some(openssl::sha::sha256(body).to_vec()),
+        "sha-512" => Some(openssl::sha::sha512(body).to_vec()),
+        _ => None,
+    }
+}
+
+/// Check the `Digest` and `Content-Digest` headers against the raw body of a request.
+///
+/// At least one digest with a supported algorithm is required, and every supported one must
+/// match; digests with other algorithms are ignored.
+pub fn verify_digest(headers: &HeaderMap, body: &[u8]) -> Result<(), errors::ActionError> {
+    let mut digests: Vec<(String, String)> = vec![];
+    for value in headers.get_all("digest") {
+        let value = value.to_str().map_err(|_e| errors::ActionError::NotAuthenticated)?;
+        for item in value.split(',') {
+            let mut parts = item.trim().splitn(2, '=');
+            if let (Some(algorithm), Some(digest)) = (parts.next(), parts.next()) {
+                digests.push((String::from(algorithm), String::from(digest)));
+            }
+        }
+    }
+    for value in headers.get_all("content-digest") {
+        let value = value.to_str().map_err(|_e| errors::ActionError::NotAuthenticated)?;
+        // A structured field dictionary of byte sequences, such as `sha-256=:<base64>:`.
+        for item in value.split(',') {
+            let mut parts = item.trim().splitn(2, '=');
+            if let (Some(algorithm), Some(digest)) = (parts.next(), parts.next()) {
+                let digest = digest.split(';').next().unwrap_or("").trim_matches(':');
+                digests.push((String::from(algorithm), String::from(digest)));
+            }
+        }
+    }
+
+    let mut verified = false;
+    for (algorithm, digest) in digests {
+        if let Some(expected) = body_digest(algorithm.trim(), body) {
+            let digest = base64::decode(digest.trim()).map_err(|_e| errors::ActionError::NotAuthenticated)?;
+            if digest.len() != expected.len() || !openssl::memcmp::eq(&digest, &expected) {
+                log::info!("digest mismatch for {}", algorithm);
+                return Err(errors::ActionError::NotAuthenticated);
+            }
+            verified = true;
+        }
+    }
+    if verified {
+        Ok(())
+    } else {
+        Err(errors::ActionError::NotAuthenticated)
+    }
+}
+
+/// Headers every signature has to cover, so that it can't be replayed on another resource, another
+/// server, or long after it was made.
+const REQUIRED_SIGNED_HEADERS: [&str; 3] = ["(request-target)", "host", "date"];
+
+/// Check that the `Date` header is at most `max_skew` from `now`.
+fn verify_date_skew(headers: &HeaderMap, max_skew: Duration, now: DateTime<Utc>) -> Result<(), errors::ActionError> {
+    let date = headers
+        .get("date")
+        .and_then(|date| date.to_str().ok())
+        .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+        .ok_or(errors::ActionError::NotAuthenticated)?;
+    if (now - date.with_timezone(&Utc)).num_seconds().abs() > max_skew.num_seconds() {
+        log::info!("signature date {} is too far from now", date);
+        return Err(errors::ActionError::NotAuthenticated);
+    }
+    Ok(())
+}
+
+/// Check that a `created` timestamp is at most `max_skew` from `now`.
+fn verify_created_skew(created: u64, max_skew: Duration, now: DateTime<Utc>) -> Result<(), errors::ActionError> {
+    if (now.timestamp() - created as i64).abs() > max_skew.num_seconds() {
+        log::info!("signature created at {} is too far from now", created);
+        return Err(errors::ActionError::NotAuthenticated);
+    }
+    Ok(())
+}
+
+/// Check that a signature covers the required headers, and was made at most `max_skew` from `now`
+/// according to both the `Date` header and its `(created)` parameter, if any.
+pub fn verify_signature_freshness(
+    signature: &hancock::Signature,
+    headers: &HeaderMap,
+    max_skew: Duration,
+    now: DateTime<Utc>,
+) -> Result<(), errors::ActionError> {
+    let signed_headers = signature.headers.as_deref().unwrap_or(&[]);
+    for required in REQUIRED_SIGNED_HEADERS.iter() {
+        if !signed_headers.iter().any(|name| name.as_str() == *required) {
+            log::info!("signature does not cover {}", required);
+            return Err(errors::ActionError::NotAuthenticated);
+        }
+    }
+
+    verify_date_skew(headers, max_skew, now)?;
+    if let Some(created) = signature.created {
+        verify_created_skew(created, max_skew, now)?;
+    }
+    Ok(())
+}
+
+/// Like `verify_signature_freshness`, for RFC 9421 signatures: the method, target and authority
+/// have to be covered, and `created` is required instead of the `Date` header, which is only
+/// checked if covered.
+pub fn verify_message_signature_freshness(
+    signature: &hancock::MessageSignature,
+    headers: &HeaderMap,
+    max_skew: Duration,
+    now: DateTime<Utc>,
+) -> Result<(), errors::ActionError> {
+    let covers_target = signature.covers("@target-uri")
+        || (signature.covers("@authority") && (signature.covers("@request-target") || signature.covers("@path")));
+    if !signature.covers("@method") || !covers_target {
+        log::info!("signature does not cover the method and target uri");
+        return Err(errors::ActionError::NotAuthenticated);
+    }
+
+    let created = signature.created.ok_or(errors::ActionError::NotAuthenticated)?;
+    verify_created_skew(created, max_skew, now)?;
+    if signature.covers("date") {
+        verify_date_skew(headers, max_skew, now)?;
+    }
+    Ok(())
+}
+
+/// The signature of a request, in either of the formats we accept.
+pub enum RequestSignature {
+    /// The `Signature` header of draft-cavage-http-signatures.
+    Cavage(hancock::Signature),
+    /// The `Signature-Input` and `Signature` headers of RFC 9421; only the first signature is used.
+    MessageSignature(hancock::MessageSignature),
+}
+
+impl RequestSignature {
+    /// Parse the signature of a request, preferring RFC 9421 if both formats are present.
+    pub fn from_headers(headers: &HeaderMap) -> Option<RequestSignature> {
+        let signature = headers.get("signature")?;
+        match headers.get("signature-input") {
+            Some(signature_input) => hancock::MessageSignature::parse(signature_input, signature)
+                .ok()
+                .and_then(|signatures| signatures.into_iter().next())
+                .map(RequestSignature::MessageSignature),
+            None => hancock::Signature::parse(signature).ok().map(RequestSignature::Cavage),
+        }
+    }
+
+    pub fn key_id(&self) -> Option<&str> {
+        match self {
+            RequestSignature::Cavage(signature) => signature.key_id.as_deref(),
+            RequestSignature::MessageSignature(signature) => signature.key_id.as_deref(),
+        }
+    }
+
+    /// Whether the signature covers a header.
+    pub fn covers(&self, name: &str) -> bool {
+        match self {
+            RequestSignature::Cavage(signature) => signature.headers.iter().flatten().any(|covered| covered.as_str() == name),
+            RequestSignature::MessageSignature(signature) => signature.covers(name),
+        }
+    }
+
+    pub fn verify_freshness(&self, headers: &HeaderMap, max_skew: Duration, now: DateTime<Utc>) -> Result<(), errors::ActionError> {
+        match self {
+            RequestSignature::Cavage(signature) => verify_signature_freshness(signature, headers, max_skew, now),
+            RequestSignature::MessageSignature(signature) => verify_message_signature_freshness(signature, headers, max_skew, now),
+        }
+    }
+
+    /// Verify the signature of a request to `target_uri` with a public key.
+    pub fn verify(&self, method: &Method, target_uri: &str, headers: &HeaderMap, public_key_pem: &str) -> bool {
+        let key = match openssl::pkey::PKey::public_key_from_pem(public_key_pem.as_bytes()) {
+            Ok(key) => key,
+            Err(_e) => return false,
+        };
+        match self {
+            RequestSignature::Cavage(signature) => {
+                let path_and_query = match target_uri.parse::<warp::http::Uri>() {
+                    Ok(uri) => uri.path_and_query().map(|path_and_query| String::from(path_and_query.as_str())),
+                    Err(_e) => None,
+                };
+                let path_and_query = match path_and_query {
+                    Some(path_and_query) => path_and_query,
+                    None => return false,
+                };
+                signature
+                    .verify(method, path_and_query.as_str(), headers, |body: &[u8], signature: &[u8]| {
+                        verify_with_algorithm(&key, None, body, signature)
+                    })
+                    .unwrap_or(false)
+            }
+            RequestSignature::MessageSignature(signature) => signature
+                .verify(method, target_uri, headers, |base: &[u8], bytes: &[u8]| {
+                    verify_with_algorithm(&key, signature.algorithm.as_deref(), base, bytes)
+                })
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Verify a signature made with one of the RFC 9421 algorithms; when the algorithm is not given,
+/// it follows from the key: Ed25519, or RSA PKCS#1 v1.5 with SHA-256.
+pub fn verify_with_algorithm(
+    key: &openssl::pkey::PKey<openssl::pkey::Public>,
+    algorithm: Option<&str>,
+    src: &[u8],
+    sig: &[u8],
+) -> Result<bool, openssl::error::ErrorStack> {
+    if key.id() == openssl::pkey::Id::ED25519 {
+        return match algorithm {
+            None | Some("ed25519") => openssl::sign::Verifier::new_without_digest(key)?.verify_oneshot(sig, src),
+            Some(_algorithm) => Ok(false),
+        };
+    }
+    match algorithm {
+        None | Some("rsa-v1_5-sha256") => do_verify(key, openssl::hash::MessageDigest::sha256(), src, sig),
+        Some("rsa-pss-sha512") => {
+            let mut verifier = openssl::sign::Verifier::new(openssl::hash::MessageDigest::sha512(), key)?;
+            verifier.set_rsa_padding(openssl::rsa::Padding::PKCS1_PSS)?;
+            verifier.set_rsa_mgf1_md(openssl::hash::MessageDigest::sha512())?;
+            verifier.set_rsa_pss_saltlen(openssl::sign::RsaPssSaltlen::custom(64))?;
+            verifier.update(src)?;
+            verifier.verify(sig)
+        }
+        Some(algorithm) => {
+            log::info!("unsupported signature algorithm {}", algorithm);
+            Ok(false)
+        }
+    }
+}
+
 pub fn must_authenticate(actor: Actor, body: Activity) -> Result<(), warp::Rejection>{
     if actor.uri.as_str() == body.actor.as_str() {
         log::info!("must_authenticate verified");
@@ -42,39 +277,46 @@ pub async fn authenticate_http_signatures(
     full_path: FullPath,
     headers: HeaderMap
 ) -> Result<Actor, warp::Rejection> {
-    let signature = headers.get("Signature").ok_or(warp::reject())?;
+    let signature = RequestSignature::from_headers(&headers).ok_or(warp::reject())?;
 
-    let signature = hancock::Signature::parse(&signature).or(Err(warp::reject()))?;
+    let actor_key_id = String::from(signature.key_id().ok_or(warp::reject())?);
 
-    let actor_key_id = signature.key_id.clone().ok_or(warp::reject())?;
+    signature
+        .verify_freshness(&headers, app_state.signature_max_skew, Utc::now())
+        .map_err(warp::reject::custom)?;
 
-    let actor = if actor_key_id.ends_with("#main-key") {
-        let actor_id = String::from(actor_key_id.split("#main-key").collect::<Vec<&str>>()[0]);
-        apub::actions::get_or_fetch_actor_by_uri(&app_state.db, actor_id.as_str()).await.ok()   
-    } else {
-        None
-    };
-
-    let actor = actor.ok_or(warp::reject())?;
-
-    let key = openssl::pkey::PKey::public_key_from_pem(actor.public_key_pem.as_bytes()).or(Err(warp::reject()))?;
-
-    let verified = signature
-        .verify(
-            &method,
-            full_path.as_str(),
-            &headers,
-            |body: &[u8], signature: &[u8]| {
-                do_verify(
-                    &key,
-                    openssl::hash::MessageDigest::sha256(),
-                    body,
-                    signature,
-                )
-            },
-        )
-        .unwrap_or(false);
-    if verified {
+    // The body is only authenticated through its digest, which has to be signed.
+    if method == Method::POST && !signature.covers("digest") && !signature.covers("content-digest") {
+        log::info!("signature of {} does not cover the digest", actor_key_id);
+        return Err(warp::reject::custom(errors::ActionError::NotAuthenticated));
+    }
+
+    // We are always served over https, even behind a proxy.
+    let host = headers.get("host").and_then(|host| host.to_str().ok()).ok_or(warp::reject())?;
+    let target_uri = format!("https://{}{}", host, full_path.as_str());
+
+    let (actor, key) = apub::actions::keys::get_or_fetch_key(&app_state.db, &app_state.local_domains, actor_key_id.as_str())
+        .await
+        .map_err(|err| {
+            log::info!("key {} not resolved: {}", actor_key_id, err);
+            warp::reject()
+        })?;
+
+    if signature.verify(&method, target_uri.as_str(), &headers, key.public_key_pem.as_str()) {
+        return Ok(actor);
+    }
+
+    // The actor may have rotated its key since it was fetched.
+    if !apub::actions::keys::can_refresh_actor(&app_state.local_domains, &actor, Utc::now()) {
+        log::info!("signature of {} does not verify", actor_key_id);
+        return Err(warp::reject());
+    }
+    log::info!("signature of {} does not verify, fetching {} again", actor_key_id, actor.uri);
+    apub::actions::refresh_actor(&app_state.db, &actor).await.map_err(|_e| warp::reject())?;
+    let (actor, key) = apub::actions::keys::get_key(&app_state.db, actor_key_id.as_str())
+        .await
+        .map_err(|_e| warp::reject())?;
+    if signature.verify(&method, target_uri.as_str(), &headers, key.public_key_pem.as_str()) {
         Ok(actor)
     } else {
         Err(warp::reject())
@@ -1,10 +1,16 @@
 use crate::apub;
 use crate::db::actions;
 use crate::db::models::Actor as ActorM;
+use crate::db::models::{ActorType, Follow, FOLLOW_PENDING};
 use crate::apub::models::Activity as ActivityS;
+use crate::apub::models::Object as ObjectS;
+use crate::handlers::apub::actors::ActorPath;
 use crate::state::AppState;
-use crate::errors::ActionError;
+use crate::errors::{ActionError, ActionResult};
+use diesel::PgConnection;
+use log;
 use serde_json::Value;
+use std::collections::HashSet;
 use std::sync::Arc;
 use warp;
 
@@ -21,32 +27,373 @@ pub fn get_uri(object: Value) -> Option<String> {
     }
 }
 
+/// Receive an activity on the shared inbox, or on the inbox of `inbox_owner`.
 pub async fn post_inbox(
     app_state: Arc<AppState>,
     domain: String,
+    inbox_owner: Option<(ActorPath, String)>,
     actor: ActorM,
     body: Value,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let activity = serde_json::from_value::<ActivityS>(body)
         .map_err(|_e| warp::reject::custom(ActionError::InvalidForm))?;
-    if actor.uri == activity.actor {
-        match activity.kind.as_str() {
-            "Create" => post_inbox_create(app_state, domain, activity).await,
-            "Follow" => post_inbox_follow(app_state, domain, activity).await,
-            "Undo" => post_inbox_undo(app_state, domain, activity).await,
-            _ => Err(warp::reject()),
+    if actor.uri != activity.actor {
+        return Err(warp::reject::custom(ActionError::NotAuthenticated));
+    }
+    // Ids are claimed below and stored with votes, reactions and shares, so they must not name
+    // activities of other servers.
+    if !activity.has_own_id() {
+        return Err(warp::reject::custom(ActionError::Forbidden));
+    }
+    // Acknowledged, so that the server of a deleted actor doesn't retry.
+    if actor.is_deleted() {
+        log::debug!("{} {} ignored: {} is deleted", activity.kind, activity.id, actor.uri);
+        return Ok(Box::new(warp::reply()) as Box<dyn warp::Reply>);
+    }
+
+    // The same activity may be delivered more than once, to several inboxes or by retries, even
+    // concurrently.
+    if !claim_activity(Arc::clone(&app_state), activity.id.clone()).await? {
+        log::debug!("{} {} already processed", activity.kind, activity.id);
+        return Ok(Box::new(warp::reply()) as Box<dyn warp::Reply>);
+    }
+
+    let activity_id = activity.id.clone();
+    let reply = apply_activity(Arc::clone(&app_state), domain, inbox_owner, activity).await;
+    if reply.is_err() {
+        // So that a failed activity can be delivered again.
+        let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+        tokio::task::spawn_blocking(move || actions::activity::release_activity(&conn, activity_id.as_str()))
+            .await
+            .unwrap_or(Err(ActionError::InternalError))
+            .map_err(warp::reject::custom)?;
+    }
+    reply
+}
+
+/// Apply an activity according to its type.
+///
+/// Only a Create needs its local recipients, the other activities are about the actors and posts
+/// they name.
+async fn apply_activity(
+    app_state: Arc<AppState>,
+    domain: String,
+    inbox_owner: Option<(ActorPath, String)>,
+    activity: ActivityS,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    log::debug!("{} {}", activity.kind, activity.id);
+    match activity.kind.as_str() {
+        "Create" => {
+            let recipients = get_local_recipients(Arc::clone(&app_state), domain.clone(), inbox_owner, &activity).await?;
+            log::debug!(
+                "{} for {:?}",
+                activity.id,
+                recipients.iter().map(|recipient| recipient.uri.as_str()).collect::<Vec<&str>>()
+            );
+            post_inbox_create(Arc::clone(&app_state), domain, recipients, activity).await
         }
-    } else {
-        Err(warp::reject::custom(ActionError::NotAuthenticated))
+        "Update" => post_inbox_update(Arc::clone(&app_state), domain, activity).await,
+        "Delete" => post_inbox_delete(Arc::clone(&app_state), domain, activity).await,
+        "Announce" => post_inbox_announce(Arc::clone(&app_state), domain, activity).await,
+        "EmojiReact" => post_inbox_react(Arc::clone(&app_state), domain, activity).await,
+        "Like" if activity.reaction_content().is_some() => post_inbox_react(Arc::clone(&app_state), domain, activity).await,
+        "Like" | "Dislike" => post_inbox_vote(Arc::clone(&app_state), domain, activity).await,
+        "Follow" => post_inbox_follow(Arc::clone(&app_state), domain, activity).await,
+        "Accept" => post_inbox_accept(Arc::clone(&app_state), domain, activity).await,
+        "Reject" => post_inbox_reject(Arc::clone(&app_state), domain, activity).await,
+        "Undo" => post_inbox_undo(Arc::clone(&app_state), domain, activity).await,
+        _ => Err(warp::reject()),
     }
 }
 
+async fn claim_activity(app_state: Arc<AppState>, activity_id: String) -> Result<bool, warp::Rejection> {
+    let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+    tokio::task::spawn_blocking(move || actions::activity::claim_activity(&conn, activity_id.as_str()))
+        .await
+        .unwrap_or(Err(ActionError::InternalError))
+        .map_err(warp::reject::custom)
+}
+
+/// Local actors an activity is delivered to: those it is addressed to, and the owner of the
+/// inbox it was posted to, if not the shared inbox.
+async fn get_local_recipients(
+    app_state: Arc<AppState>,
+    domain: String,
+    inbox_owner: Option<(ActorPath, String)>,
+    activity: &ActivityS,
+) -> Result<Vec<ActorM>, warp::Rejection> {
+    let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+    let addressees = activity.addressees();
+
+    tokio::task::spawn_blocking(move || {
+        let mut recipients = apub::actions::inbox::local_recipients(&conn, &app_state.local_domains, &addressees)?;
+        if let Some((actor_path, name)) = inbox_owner {
+            let owner = actor_path.get_actor(&conn, name.as_str(), domain.as_str())?;
+            if !recipients.iter().any(|recipient| recipient.id == owner.id) {
+                recipients.insert(0, owner);
+            }
+        }
+        Ok(recipients)
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError))
+    .map_err(|err| match err {
+        ActionError::NotFound => warp::reject::not_found(),
+        err => warp::reject::custom(err),
+    })
+}
+
 pub async fn post_inbox_create(
-    _app_state: Arc<AppState>,
+    app_state: Arc<AppState>,
+    _domain: String,
+    recipients: Vec<ActorM>,
+    activity: ActivityS,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let object = match &activity.object {
+        Value::String(uri) => apub::actions::posts::fetch_object(uri.as_str()).await,
+        object => serde_json::from_value::<ObjectS>(object.clone()).or(Err(ActionError::InvalidForm)),
+    }
+    .map_err(|err| warp::reject::custom(err))?;
+
+    // Replies are relevant to the threads they belong to, whoever they are addressed to.
+    if recipients.is_empty() && object.in_reply_to.is_none() {
+        log::info!("dropped {}: not addressed to any local actor", object.id);
+        return Ok(Box::new(warp::reply()));
+    }
+
+    // Only the author may create an object.
+    if object.attributed_to != activity.actor {
+        return Err(warp::reject::custom(ActionError::NotAuthenticated));
+    }
+    let author = apub::actions::get_or_fetch_actor_by_uri(&app_state.db, object.attributed_to.as_str())
+        .await
+        .map_err(|err| warp::reject::custom(err))?;
+
+    // Relays of the activity should carry the object, even if it had to be fetched.
+    let activity = ActivityS {
+        object: serde_json::to_value(&object).map_err(|_e| warp::reject::custom(ActionError::InternalError))?,
+        ..activity
+    };
+    let post = apub::actions::posts::receive_post(&app_state, &author, &object, Some(&activity))
+        .await
+        .map_err(|err| warp::reject::custom(err))?;
+    if post.is_none() {
+        log::info!("dropped {}: not in any known community or thread", object.id);
+    }
+
+    Ok(Box::new(warp::reply()))
+}
+
+/// Apply an Update by an actor of its own document, or of a post it wrote.
+pub async fn post_inbox_update(
+    app_state: Arc<AppState>,
+    _domain: String,
+    activity: ActivityS,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let object = match &activity.object {
+        Value::String(uri) if uri == &activity.actor => {
+            // Without the document, it has to be fetched again, once.
+            let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+            let actor_uri = uri.clone();
+            let known = tokio::task::spawn_blocking(move || actions::actor::get_actor_by_uri(&conn, actor_uri.as_str()))
+                .await
+                .unwrap_or(Err(ActionError::InternalError));
+            match known {
+                Ok(actor) => apub::actions::refresh_actor(&app_state.db, &actor).await,
+                Err(ActionError::NotFound) => apub::actions::fetch_actor_by_uri(&app_state.db, uri.as_str()).await,
+                Err(err) => Err(err),
+            }
+            .map_err(warp::reject::custom)?;
+            return Ok(Box::new(warp::reply()));
+        }
+        Value::String(uri) => serde_json::to_value(apub::actions::posts::fetch_object(uri.as_str()).await.map_err(warp::reject::custom)?)
+            .map_err(|_e| warp::reject::custom(ActionError::InternalError))?,
+        object => object.clone(),
+    };
+
+    // Only an actor may update itself, and only the author may edit a post.
+    let may_update = activity.may_update(&object);
+    match object["type"].as_str().unwrap_or_default() {
+        "Person" | "Group" | "Service" | "Application" => {
+            if !may_update {
+                return Err(warp::reject::custom(ActionError::NotAuthenticated));
+            }
+            let actor = serde_json::from_value::<apub::models::Actor>(object)
+                .map_err(|_e| warp::reject::custom(ActionError::InvalidForm))?;
+            apub::actions::receive_actor_update(&app_state.db, &actor)
+                .await
+                .map_err(warp::reject::custom)?;
+        }
+        kind if apub::models::POST_KINDS.contains(&kind) => {
+            if !may_update {
+                return Err(warp::reject::custom(ActionError::NotAuthenticated));
+            }
+            let object = serde_json::from_value::<ObjectS>(object)
+                .map_err(|_e| warp::reject::custom(ActionError::InvalidForm))?;
+            let author = apub::actions::get_or_fetch_actor_by_uri(&app_state.db, object.attributed_to.as_str())
+                .await
+                .map_err(warp::reject::custom)?;
+            let activity = ActivityS {
+                object: serde_json::to_value(&object).map_err(|_e| warp::reject::custom(ActionError::InternalError))?,
+                ..activity
+            };
+            let post = apub::actions::posts::receive_post_update(&app_state, &author, &object, &activity)
+                .await
+                .map_err(warp::reject::custom)?;
+            if post.is_none() {
+                log::info!("dropped update of {}: unknown, deleted or outdated", object.id);
+            }
+        }
+        kind => log::info!("dropped update of {:?}: unsupported type {}", object["id"], kind),
+    }
+
+    Ok(Box::new(warp::reply()))
+}
+
+/// Apply a Delete by an actor of itself, or of a post it wrote.
+pub async fn post_inbox_delete(
+    app_state: Arc<AppState>,
+    _domain: String,
+    activity: ActivityS,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let uri = get_uri(activity.object.clone()).ok_or_else(|| warp::reject::custom(ActionError::InvalidForm))?;
+
+    if uri == activity.actor {
+        let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+        let app_state_move = Arc::clone(&app_state);
+        tokio::task::spawn_blocking(move || {
+            let actor = actions::actor::get_actor_by_uri(&conn, uri.as_str())?;
+            // Local actors are only deleted by their own server.
+            if app_state_move.local_domains.contains(&actor.domain) {
+                return Err(ActionError::Forbidden);
+            }
+            actions::actor::delete_actor(&conn, &actor)
+        })
+        .await
+        .unwrap_or(Err(ActionError::InternalError))
+        .map_err(warp::reject::custom)?;
+        return Ok(Box::new(warp::reply()));
+    }
+
+    let author = apub::actions::get_or_fetch_actor_by_uri(&app_state.db, activity.actor.as_str())
+        .await
+        .map_err(warp::reject::custom)?;
+    let post = apub::actions::posts::receive_post_delete(&app_state, &author, uri.as_str(), &activity)
+        .await
+        .map_err(warp::reject::custom)?;
+    if post.is_none() {
+        log::info!("dropped delete of {}: unknown or already deleted", uri);
+    }
+
+    Ok(Box::new(warp::reply()))
+}
+
+/// Store a post announced by a community, or shared by someone, after fetching it from its
+/// origin; shares by others than communities are counted.
+pub async fn post_inbox_announce(
+    app_state: Arc<AppState>,
     _domain: String,
-    _activity: ActivityS,
+    activity: ActivityS,
 ) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
-    Err(warp::reject())
+    let announcer = apub::actions::get_or_fetch_actor_by_uri(&app_state.db, activity.actor.as_str())
+        .await
+        .map_err(warp::reject::custom)?;
+    let is_community = matches!(ActorType::from(&announcer), ActorType::Group);
+
+    // Only the posts of communities followed from here are taken in.
+    if is_community {
+        let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+        let local_domains = app_state.local_domains.clone();
+        let community = announcer.clone();
+        let followed = tokio::task::spawn_blocking(move || actions::follow::actor_has_local_followers(&conn, &local_domains, &community))
+            .await
+            .unwrap_or(Err(ActionError::InternalError))
+            .map_err(warp::reject::custom)?;
+        if !followed {
+            log::info!("dropped {} announced by {}: not followed", activity.id, announcer.uri);
+            return Ok(Box::new(warp::reply()));
+        }
+    }
+
+    // Communities announce the activities happening in them (FEP-1b12), and others the posts
+    // they share; the embedded objects are only trusted once fetched from their origin.
+    let (post_uri, relayed) = match &activity.object {
+        Value::Object(object) if object.contains_key("actor") => {
+            let relayed = serde_json::from_value::<ActivityS>(activity.object.clone())
+                .map_err(|_e| warp::reject::custom(ActionError::InvalidForm))?;
+            if !is_community || !["Create", "Update"].contains(&relayed.kind.as_str()) {
+                log::info!("dropped {} announced by {}: unsupported", relayed.kind, announcer.uri);
+                return Ok(Box::new(warp::reply()));
+            }
+            let post_uri = get_uri(relayed.object.clone()).ok_or_else(|| warp::reject::custom(ActionError::InvalidForm))?;
+            (post_uri, relayed)
+        }
+        object => (get_uri(object.clone()).ok_or_else(|| warp::reject::custom(ActionError::InvalidForm))?, activity.clone()),
+    };
+
+    let post = apub::actions::posts::receive_announced_post(&app_state, post_uri.as_str(), &relayed)
+        .await
+        .map_err(warp::reject::custom)?;
+    let post = match post {
+        Some(post) => post,
+        None => {
+            log::info!("dropped {} announced by {}: not in any known community or thread", post_uri, announcer.uri);
+            return Ok(Box::new(warp::reply()));
+        }
+    };
+
+    if !is_community {
+        let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+        let activity_id = activity.id.clone();
+        tokio::task::spawn_blocking(move || actions::share::add_share(&conn, &announcer, &post, Some(activity_id.as_str())))
+            .await
+            .unwrap_or(Err(ActionError::InternalError))
+            .map_err(warp::reject::custom)?;
+    }
+
+    Ok(Box::new(warp::reply()))
+}
+
+/// Record an EmojiReact, or a Like with content, as a reaction to a post.
+pub async fn post_inbox_react(
+    app_state: Arc<AppState>,
+    _domain: String,
+    activity: ActivityS,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let post_uri = get_uri(activity.object.clone()).ok_or_else(|| warp::reject::custom(ActionError::InvalidForm))?;
+
+    let reactor = apub::actions::get_or_fetch_actor_by_uri(&app_state.db, activity.actor.as_str())
+        .await
+        .map_err(warp::reject::custom)?;
+    let reaction = apub::actions::reactions::receive_reaction(&app_state, &reactor, post_uri.as_str(), &activity)
+        .await
+        .map_err(warp::reject::custom)?;
+    if reaction.is_none() {
+        log::info!("dropped reaction to {}: unknown, deleted or removed post", post_uri);
+    }
+
+    Ok(Box::new(warp::reply()))
+}
+
+/// Record a Like or a Dislike of a post as a vote.
+pub async fn post_inbox_vote(
+    app_state: Arc<AppState>,
+    _domain: String,
+    activity: ActivityS,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let post_uri = get_uri(activity.object.clone()).ok_or_else(|| warp::reject::custom(ActionError::InvalidForm))?;
+
+    let voter = apub::actions::get_or_fetch_actor_by_uri(&app_state.db, activity.actor.as_str())
+        .await
+        .map_err(warp::reject::custom)?;
+    let vote = apub::actions::votes::receive_vote(&app_state, &voter, post_uri.as_str(), &activity)
+        .await
+        .map_err(warp::reject::custom)?;
+    if vote.is_none() {
+        log::info!("dropped vote on {}: unknown, deleted or removed post", post_uri);
+    }
+
+    Ok(Box::new(warp::reply()))
 }
 
 pub async fn post_inbox_follow(
@@ -55,17 +402,101 @@ pub async fn post_inbox_follow(
     activity: ActivityS,
 ) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
     let actor_id = activity.actor.clone();
-    let object_id = activity.object.clone();
-    let object_id = object_id.as_str().ok_or(warp::reject())?.to_owned();
-    let actor_id = actor_id.as_str().to_owned();
+    let object_id = get_uri(activity.object.clone()).ok_or(warp::reject())?;
+    let activity_id = activity.id.clone();
 
     let conn = app_state.db.get().map_err(|_e| warp::reject())?;
+    let app_state_move = Arc::clone(&app_state);
 
-    let _result = tokio::task::spawn_blocking(move || {
-        actions::follow::follow_actor_by_uri(&conn, &actor_id, &object_id).or(Err(warp::reject()))
+    tokio::task::spawn_blocking(move || {
+        let following = actions::actor::get_actor_by_uri(&conn, &object_id)?;
+        if !app_state_move.local_domains.contains(&following.domain) {
+            return Err(ActionError::NotFound);
+        }
+        let follower = actions::actor::get_actor_by_uri(&conn, &actor_id)?;
+        let follow = actions::follow::follow_actor_by_uri(&conn, &actor_id, &object_id, Some(&activity_id))?;
+
+        // Locked actors answer once a local user approves or rejects the request.
+        if follow.role != FOLLOW_PENDING {
+            apub::actions::follow::send_accept_follow(&conn, &follower, &following, &follow)?;
+        }
+        Ok(())
     })
     .await
-    .unwrap_or(Err(warp::reject()))?;
+    .unwrap_or(Err(ActionError::InternalError))
+    .map_err(|err| warp::reject::custom(err))?;
+
+    Ok(Box::new(warp::reply()))
+}
+
+/// Find the follow by a local actor which an Accept or Reject answers.
+fn get_answered_follow(
+    conn: &PgConnection,
+    local_domains: &HashSet<String>,
+    activity: &ActivityS,
+) -> ActionResult<(ActorM, ActorM, Follow)> {
+    let follow = match get_uri(activity.object.clone()).map(|uri| actions::follow::get_follow_by_uri(conn, &uri)) {
+        Some(Ok(follow)) => follow,
+        _ => {
+            // Not every server keeps the id of the Follow; match it by its actor and object then.
+            let follow_activity: ActivityS = serde_json::from_value(activity.object.clone())
+                .map_err(|_e| ActionError::InvalidForm)?;
+            if follow_activity.kind != "Follow" {
+                return Err(ActionError::InvalidForm);
+            }
+            let follower = actions::actor::get_actor_by_uri(conn, &follow_activity.actor)?;
+            let following_uri = get_uri(follow_activity.object).ok_or(ActionError::InvalidForm)?;
+            let following = actions::actor::get_actor_by_uri(conn, &following_uri)?;
+            actions::follow::get_follow(conn, &follower, &following)?
+        }
+    };
+
+    let follower = actions::actor::get_actor_by_id(conn, follow.follower_id)?;
+    let following = actions::actor::get_actor_by_id(conn, follow.following_id)?;
+    if following.uri != activity.actor || !local_domains.contains(&follower.domain) {
+        return Err(ActionError::NotAuthenticated);
+    }
+    Ok((follower, following, follow))
+}
+
+async fn post_inbox_accept(
+    app_state: Arc<AppState>,
+    _domain: String,
+    activity: ActivityS,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let conn = app_state.db.get().map_err(|_e| warp::reject())?;
+    let app_state_move = Arc::clone(&app_state);
+
+    tokio::task::spawn_blocking(move || {
+        let (follower, following, follow) = get_answered_follow(&conn, &app_state_move.local_domains, &activity)?;
+        if follow.role == FOLLOW_PENDING {
+            actions::follow::approve_follow(&conn, &follower, &following)?;
+        }
+        Ok(())
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError))
+    .map_err(|err| warp::reject::custom(err))?;
+
+    Ok(Box::new(warp::reply()))
+}
+
+async fn post_inbox_reject(
+    app_state: Arc<AppState>,
+    _domain: String,
+    activity: ActivityS,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let conn = app_state.db.get().map_err(|_e| warp::reject())?;
+    let app_state_move = Arc::clone(&app_state);
+
+    tokio::task::spawn_blocking(move || {
+        // A follow may be rejected after being accepted, to remove the follower.
+        let (follower, following, _follow) = get_answered_follow(&conn, &app_state_move.local_domains, &activity)?;
+        actions::follow::unfollow_actor_by_uri(&conn, &follower.uri, &following.uri)
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError))
+    .map_err(|err| warp::reject::custom(err))?;
 
     Ok(Box::new(warp::reply()))
 }
@@ -79,10 +510,97 @@ async fn post_inbox_undo(
 
     match object_activity.kind.as_str() {
         "Follow" => post_inbox_undo_follow(app_state, domain, activity).await,
+        "Announce" => post_inbox_undo_announce(app_state, domain, activity).await,
+        "EmojiReact" => post_inbox_undo_react(app_state, domain, activity).await,
+        "Like" if object_activity.reaction_content().is_some() => post_inbox_undo_react(app_state, domain, activity).await,
+        "Like" | "Dislike" => post_inbox_undo_vote(app_state, domain, activity).await,
         _ => Err(warp::reject()),
     }
 }
 
+async fn post_inbox_undo_announce(
+    app_state: Arc<AppState>,
+    _domain: String,
+    activity: ActivityS,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let object_activity: ActivityS = serde_json::from_value(activity.object.clone()).or(Err(warp::reject::custom(ActionError::InvalidForm)))?;
+    // Only the sharer may undo its share.
+    if object_activity.actor != activity.actor {
+        return Err(warp::reject::custom(ActionError::NotAuthenticated));
+    }
+    let post_uri = get_uri(object_activity.object).ok_or_else(|| warp::reject::custom(ActionError::InvalidForm))?;
+    let actor_id = activity.actor.clone();
+
+    let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+    let result = tokio::task::spawn_blocking(move || {
+        let sharer = actions::actor::get_actor_by_uri(&conn, actor_id.as_str())?;
+        let post = actions::post::get_post_by_uri(&conn, post_uri.as_str())?;
+        actions::share::remove_share(&conn, &sharer, &post)
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError));
+
+    match result {
+        Ok(_share) => Ok(Box::new(warp::reply())),
+        Err(ActionError::NotFound) => {
+            log::info!("dropped undo of announce {}: no such share", object_activity.id);
+            Ok(Box::new(warp::reply()))
+        }
+        Err(err) => Err(warp::reject::custom(err)),
+    }
+}
+
+async fn post_inbox_undo_react(
+    app_state: Arc<AppState>,
+    _domain: String,
+    activity: ActivityS,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let object_activity: ActivityS = serde_json::from_value(activity.object.clone()).or(Err(warp::reject::custom(ActionError::InvalidForm)))?;
+    // Only the reactor may undo its reaction.
+    if object_activity.actor != activity.actor {
+        return Err(warp::reject::custom(ActionError::NotAuthenticated));
+    }
+
+    let reactor = apub::actions::get_or_fetch_actor_by_uri(&app_state.db, activity.actor.as_str())
+        .await
+        .map_err(warp::reject::custom)?;
+    let reaction = apub::actions::reactions::receive_undo_reaction(&app_state, &reactor, &object_activity, &activity)
+        .await
+        .map_err(warp::reject::custom)?;
+    if reaction.is_none() {
+        log::info!("dropped undo of reaction {}: no such reaction", object_activity.id);
+    }
+
+    Ok(Box::new(warp::reply()))
+}
+
+async fn post_inbox_undo_vote(
+    app_state: Arc<AppState>,
+    _domain: String,
+    activity: ActivityS,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let object_activity: ActivityS = serde_json::from_value(activity.object.clone()).or(Err(warp::reject::custom(ActionError::InvalidForm)))?;
+    // Only the voter may undo its vote.
+    if object_activity.actor != activity.actor {
+        return Err(warp::reject::custom(ActionError::NotAuthenticated));
+    }
+    let score = apub::actions::votes::vote_score(object_activity.kind.as_str())
+        .ok_or_else(|| warp::reject::custom(ActionError::InvalidForm))?;
+    let post_uri = get_uri(object_activity.object.clone()).ok_or_else(|| warp::reject::custom(ActionError::InvalidForm))?;
+
+    let voter = apub::actions::get_or_fetch_actor_by_uri(&app_state.db, activity.actor.as_str())
+        .await
+        .map_err(warp::reject::custom)?;
+    let vote = apub::actions::votes::receive_undo_vote(&app_state, &voter, post_uri.as_str(), score, &object_activity, &activity)
+        .await
+        .map_err(warp::reject::custom)?;
+    if vote.is_none() {
+        log::info!("dropped undo of vote on {}: no such vote", post_uri);
+    }
+
+    Ok(Box::new(warp::reply()))
+}
+
 async fn post_inbox_undo_follow(
     app_state: Arc<AppState>,
     _domain: String,
@@ -0,0 +1,87 @@
+use crate::apub;
+use crate::db::actions;
+use crate::errors::ActionError;
+use crate::state::AppState;
+
+use tokio;
+use warp;
+use warp::Reply;
+use warp::http::StatusCode;
+use std::sync::Arc;
+
+pub async fn get_post(
+    app_state: Arc<AppState>,
+    domain: String,
+    id: String
+) -> Result<impl warp::Reply, warp::Rejection> {
+    get_post_by_path(app_state, domain, "posts", id).await
+}
+
+pub async fn get_comment(
+    app_state: Arc<AppState>,
+    domain: String,
+    id: String
+) -> Result<impl warp::Reply, warp::Rejection> {
+    get_post_by_path(app_state, domain, "comments", id).await
+}
+
+/// Serve the post whose URI is `https://<domain>/<slug>/<id>`, or its tombstone if deleted.
+async fn get_post_by_path(
+    app_state: Arc<AppState>,
+    domain: String,
+    slug: &str,
+    id: String
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+    let uri = format!("https://{}/{}/{}", domain, slug, id);
+
+    let result = tokio::task::spawn_blocking(move || {
+        let post = actions::post::get_post_by_uri(&conn, uri.as_str())?;
+        actions::post::get_post_view(&conn, post)
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError));
+
+    let post_view = match result {
+        Ok(post_view) => post_view,
+        Err(err) => return Ok(err.into_response()),
+    };
+
+    if post_view.post.is_deleted() || post_view.post.is_removed {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&apub::models::post_tombstone(&post_view.post)),
+            StatusCode::GONE,
+        ).into_response());
+    }
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&apub::models::Object::from(&post_view)),
+        StatusCode::OK,
+    ).into_response())
+}
+
+pub async fn get_activity(
+    app_state: Arc<AppState>,
+    domain: String,
+    id: String
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let conn = app_state.db.get().map_err(|_e| warp::reject::custom(ActionError::InternalError))?;
+    let uri = format!("https://{}/activities/{}", domain, id);
+
+    let result = tokio::task::spawn_blocking(move || {
+        let activity = actions::activity::get_activity_by_uri(&conn, uri.as_str())?;
+        // Only public activities are served, like in the outbox.
+        if !activity.is_public {
+            return Err(ActionError::NotFound);
+        }
+        serde_json::from_str::<serde_json::Value>(activity.body.as_str())
+            .map_err(|_e| ActionError::InternalError)
+    })
+    .await
+    .unwrap_or(Err(ActionError::InternalError));
+
+    match result {
+        Ok(body) => Ok(warp::reply::json(&body).into_response()),
+        Err(err) => Ok(err.into_response()),
+    }
+}
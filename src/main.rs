@@ -1,6 +1,9 @@
 #[macro_use]
 extern crate diesel;
 
+use handlers::api::moderation::PostModeration;
+use handlers::api::votes::VoteAction;
+use handlers::apub::actors::ActorPath;
 use state::AppState;
 
 mod apub;
@@ -22,6 +25,9 @@ async fn main() {
     pretty_env_logger::init();
 
     let app_state = Arc::new(AppState::new());
+    apub::actions::delivery::spawn_delivery_workers(Arc::clone(&app_state));
+    apub::actions::housekeeping::spawn_housekeeping(Arc::clone(&app_state));
+
     let app_state = warp::any().map(move || Arc::clone(&app_state));
     let with_app_state_and_host = warp::any().and(app_state.clone()).and(
         app_state
@@ -32,39 +38,78 @@ async fn main() {
     let get_user = with_app_state_and_host
         .clone()
         .and(warp::get())
+        .and(warp::any().map(|| ActorPath::Users))
         .and(warp::path!("users" / String))
         .and_then(handlers::apub::actors::get_user);
     let get_communities = with_app_state_and_host
         .clone()
         .and(warp::get())
+        .and(warp::any().map(|| ActorPath::Communities))
         .and(warp::path!("communities" / String))
         .and_then(handlers::apub::actors::get_user);
 
     let get_user_outbox = with_app_state_and_host
         .clone()
         .and(warp::get())
+        .and(warp::any().map(|| ActorPath::Users))
         .and(warp::path!("users" / String / "outbox"))
+        .and(warp::query())
         .and_then(handlers::apub::actors::get_user_outbox);
     let get_communities_outbox = with_app_state_and_host
         .clone()
         .and(warp::get())
+        .and(warp::any().map(|| ActorPath::Communities))
         .and(warp::path!("communities" / String / "outbox"))
+        .and(warp::query())
         .and_then(handlers::apub::actors::get_user_outbox);
 
     // Actor followers
     let get_user_followers = with_app_state_and_host
         .clone()
         .and(warp::get())
+        .and(warp::any().map(|| ActorPath::Users))
         .and(warp::path!("users" / String / "followers"))
         .and(warp::query())
         .and_then(handlers::apub::actors::get_user_followers);
     let get_communities_followers = with_app_state_and_host
         .clone()
         .and(warp::get())
+        .and(warp::any().map(|| ActorPath::Communities))
         .and(warp::path!("communities" / String / "followers"))
         .and(warp::query())
         .and_then(handlers::apub::actors::get_user_followers);
 
+    // Community moderators
+    let get_communities_moderators = with_app_state_and_host
+        .clone()
+        .and(warp::get())
+        .and(warp::path!("communities" / String / "moderators"))
+        .and_then(handlers::apub::actors::get_community_moderators);
+
+    // Actor following
+    let get_user_following = with_app_state_and_host
+        .clone()
+        .and(warp::get())
+        .and(warp::path!("users" / String / "following"))
+        .and(warp::query())
+        .and_then(handlers::apub::actors::get_user_following);
+
+    // Objects and activities, so that their ids can be dereferenced
+    let get_post = with_app_state_and_host
+        .clone()
+        .and(warp::get())
+        .and(warp::path!("posts" / String))
+        .and_then(handlers::apub::objects::get_post);
+    let get_comment = with_app_state_and_host
+        .clone()
+        .and(warp::get())
+        .and(warp::path!("comments" / String))
+        .and_then(handlers::apub::objects::get_comment);
+    let get_activity = with_app_state_and_host
+        .clone()
+        .and(warp::get())
+        .and(warp::path!("activities" / String))
+        .and_then(handlers::apub::objects::get_activity);
 
     let get_webfinger = with_app_state_and_host
         .clone()
@@ -79,19 +124,173 @@ async fn main() {
         .or(get_user_followers)
         .or(get_communities_outbox)
         .or(get_communities_followers)
+        .or(get_user_following)
+        .or(get_communities_moderators)
+        .or(get_post)
+        .or(get_comment)
+        .or(get_activity)
         .map(handlers::apub::map_content_type_ap);
 
     let post_inbox = with_app_state_and_host
         .clone()
         .and(warp::post())
-        .and(warp::path("inbox").or(warp::path!("users" / String / "inbox").map(|_s| ()).untuple_one()).unify())
+        .and(
+            warp::path!("inbox")
+                .map(|| None)
+                .or(warp::path!("users" / String / "inbox").map(|name| Some((ActorPath::Users, name))))
+                .unify()
+                .or(warp::path!("communities" / String / "inbox").map(|name| Some((ActorPath::Communities, name))))
+                .unify(),
+        )
         .and(filter_auth_http_signatures(with_app_state_and_host.clone().boxed()))
         .and(handlers::apub::activity_json())
         .and_then(handlers::apub::inbox::post_inbox);
 
     let ap_routes = ap_routes.or(post_inbox);
 
-    warp::serve(ap_routes.or(get_webfinger))
+    // Follow requests of locked actors
+    let get_follow_requests = with_app_state_and_host
+        .clone()
+        .and(warp::get())
+        .and(warp::path!("api" / "v1" / "follow_requests"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::query())
+        .and_then(handlers::api::follows::get_follow_requests);
+    let post_follow_requests_authorize = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "follow_requests" / "authorize"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::follows::authorize_follow_request);
+    let post_follow_requests_reject = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "follow_requests" / "reject"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::follows::reject_follow_request);
+
+    // Follows of local users
+    let post_follow = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "follows"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::follows::post_follow);
+    let post_undo_follow = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "follows" / "undo"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::follows::post_undo_follow);
+
+    // Moderation of communities
+    let post_community_moderators = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "communities" / String / "moderators"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::moderation::post_add_moderator);
+    let post_community_moderators_remove = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "communities" / String / "moderators" / "remove"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::moderation::post_remove_moderator);
+    let post_community_bans = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "communities" / String / "bans"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::moderation::post_ban);
+    let post_community_bans_undo = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "communities" / String / "bans" / "undo"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::moderation::post_undo_ban);
+    let post_posts_remove = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "posts" / "remove"))
+        .and(warp::any().map(|| PostModeration::Remove))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::moderation::post_moderate_post);
+    let post_posts_restore = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "posts" / "restore"))
+        .and(warp::any().map(|| PostModeration::Restore))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::moderation::post_moderate_post);
+    let post_posts_pin = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "posts" / "pin"))
+        .and(warp::any().map(|| PostModeration::Pin))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::moderation::post_moderate_post);
+    let post_posts_unpin = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "posts" / "unpin"))
+        .and(warp::any().map(|| PostModeration::Unpin))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::moderation::post_moderate_post);
+
+    let post_posts_upvote = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "posts" / "upvote"))
+        .and(warp::any().map(|| VoteAction::Up))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::votes::post_vote);
+    let post_posts_downvote = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "posts" / "downvote"))
+        .and(warp::any().map(|| VoteAction::Down))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::votes::post_vote);
+    let post_posts_unvote = with_app_state_and_host
+        .clone()
+        .and(warp::post())
+        .and(warp::path!("api" / "v1" / "posts" / "unvote"))
+        .and(warp::any().map(|| VoteAction::Undo))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handlers::api::votes::post_vote);
+    let api_routes = get_follow_requests
+        .or(post_follow_requests_authorize)
+        .or(post_follow_requests_reject)
+        .or(post_follow)
+        .or(post_undo_follow)
+        .or(post_community_moderators)
+        .or(post_community_moderators_remove)
+        .or(post_community_bans)
+        .or(post_community_bans_undo)
+        .or(post_posts_remove)
+        .or(post_posts_restore)
+        .or(post_posts_pin)
+        .or(post_posts_unpin)
+        .or(post_posts_upvote)
+        .or(post_posts_downvote)
+        .or(post_posts_unvote);
+
+    warp::serve(ap_routes.or(api_routes).or(get_webfinger))
         .run(([0, 0, 0, 0], 8000))
         .await;
 }
@@ -4,6 +4,7 @@ use dotenv;
 use std::env;
 use r2d2;
 use std::collections::HashSet;
+use chrono::Duration;
 
 pub type DbPool = r2d2::Pool<ConnectionManager<PgConnection>>;
 
@@ -13,6 +14,16 @@ pub struct AppState {
     pub website_name: String,
     pub db: DbPool,
     pub local_domains: HashSet<String>,
+    /// Number of tokio tasks sending queued deliveries.
+    pub delivery_workers: usize,
+    /// A delivery still failing after this long is given up.
+    pub delivery_give_up_after: Duration,
+    /// How far the `Date` or `(created)` of a signed request may be from now.
+    pub signature_max_skew: Duration,
+    /// How long the ids of received activities are remembered, to ignore them if delivered again.
+    pub processed_activity_retention: Duration,
+    /// Remote actors are fetched again once they haven't been for this long.
+    pub actor_refresh_interval: Duration,
 }
 
 impl AppState {
@@ -27,13 +38,39 @@ impl AppState {
             local_domain_set.insert(String::from(local_domain));
         }
 
-        // Create connection pool
-        let pool: DbPool = r2d2::Pool::builder().max_size(1).build(ConnectionManager::new(db_url)).expect("Failed to create pool.");
+        let delivery_workers = env::var("DELIVERY_WORKERS")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(4);
+        let delivery_give_up_hours = env::var("DELIVERY_GIVE_UP_HOURS")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(48);
+        let signature_max_skew_secs = env::var("SIGNATURE_MAX_SKEW_SECS")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(300);
+        let processed_activity_retention_days = env::var("PROCESSED_ACTIVITY_RETENTION_DAYS")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(7);
+        let actor_refresh_hours = env::var("ACTOR_REFRESH_HOURS")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(24);
+
+        // Create connection pool, with a connection for each delivery worker besides the handlers.
+        let pool: DbPool = r2d2::Pool::builder().max_size(delivery_workers as u32 + 4).build(ConnectionManager::new(db_url)).expect("Failed to create pool.");
 
         AppState {
             website_name: String::from("Commune"),
             db: pool,
             local_domains: local_domain_set,
+            delivery_workers,
+            delivery_give_up_after: Duration::hours(delivery_give_up_hours),
+            signature_max_skew: Duration::seconds(signature_max_skew_secs),
+            processed_activity_retention: Duration::days(processed_activity_retention_days),
+            actor_refresh_interval: Duration::hours(actor_refresh_hours),
         }
     }
 }
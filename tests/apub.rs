@@ -8,4 +8,471 @@ fn test_deserialize_paged_collection_query_string() -> Result<(), Error> {
     let paged_collection: PagedCollection = from_str("page=1")?;
     assert_eq!(paged_collection.page_number(), 1);
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_sign_post_headers_verifies() {
+    use commune::apub::actions::delivery::sign_post_headers;
+    use commune::apub::rsa::generate_key_pair_pem;
+    use commune::handlers::apub::auth::do_verify;
+    use commune::hancock::Signature;
+    use warp::http::Method;
+
+    let keypair = generate_key_pair_pem().unwrap();
+    let uri = url::Url::parse("https://test1.example.tld/users/misaka4e22/inbox").unwrap();
+    let body = br#"{"type":"Follow"}"#;
+    let headers = sign_post_headers("https://test2.example.tld/users/misaka4e21#main-key", &keypair.private, &uri, body).unwrap();
+    assert_eq!(headers.get("host").unwrap(), "test1.example.tld");
+    assert_eq!(headers.get("digest").unwrap(), "SHA-256=GYwYnH3BiO6aICFt0ThC5bUIJ4byvqdpWtR8m5fNkww=");
+
+    let signature = Signature::parse(headers.get("signature").unwrap()).unwrap();
+    assert_eq!(signature.key_id.as_deref(), Some("https://test2.example.tld/users/misaka4e21#main-key"));
+    let key = openssl::pkey::PKey::public_key_from_pem(keypair.public.as_bytes()).unwrap();
+    let verified = signature.verify(&Method::POST, uri.path(), &headers, |src, sig| {
+        do_verify(&key, openssl::hash::MessageDigest::sha256(), src, sig)
+    }).unwrap();
+    assert!(verified);
+}
+
+#[test]
+fn test_deserialize_lemmy_page() {
+    use commune::apub::models::{is_same_origin, Object, PUBLIC};
+
+    let page: Object = serde_json::from_value(serde_json::json!({
+        "type": "Page",
+        "id": "https://test2.example.tld/post/1",
+        "attributedTo": "https://test2.example.tld/u/misaka4e21",
+        "to": ["https://test1.example.tld/communities/railgun", PUBLIC],
+        "name": "Level 5",
+        "url": {"type": "Link", "href": "https://test3.example.tld/article"},
+        "audience": "https://test1.example.tld/communities/railgun",
+        "published": "2021-03-20T05:28:17.123456+00:00"
+    })).unwrap();
+    assert!(page.is_post());
+    assert_eq!(page.content, "");
+    assert_eq!(page.name.as_deref(), Some("Level 5"));
+    assert_eq!(page.url_string().as_deref(), Some("https://test3.example.tld/article"));
+    assert_eq!(page.recipients(), vec![String::from("https://test1.example.tld/communities/railgun"), String::from(PUBLIC)]);
+    assert!(is_same_origin(&page.id, &page.attributed_to));
+    assert!(!is_same_origin(&page.id, "https://test1.example.tld/communities/railgun"));
+}
+
+#[test]
+fn test_activity_addressees() {
+    use commune::apub::models::Activity;
+
+    let activity: Activity = serde_json::from_value(serde_json::json!({
+        "type": "Create",
+        "id": "https://test2.example.tld/activities/1",
+        "actor": "https://test2.example.tld/users/misaka4e21",
+        "to": "https://www.w3.org/ns/activitystreams#Public",
+        "cc": ["https://test2.example.tld/users/misaka4e21/followers"],
+        "object": {
+            "type": "Note",
+            "id": "https://test2.example.tld/notes/1",
+            "to": ["https://www.w3.org/ns/activitystreams#Public"],
+            "cc": [{"type": "Group", "id": "https://test1.example.tld/communities/railgun"}],
+            "audience": "https://test1.example.tld/communities/railgun"
+        }
+    })).unwrap();
+    assert_eq!(activity.addressees(), vec![
+        "https://www.w3.org/ns/activitystreams#Public",
+        "https://test2.example.tld/users/misaka4e21/followers",
+        "https://test1.example.tld/communities/railgun",
+    ]);
+}
+
+#[test]
+fn test_verify_digest() {
+    use commune::apub::actions::delivery::digest_header_value;
+    use commune::handlers::apub::auth::verify_digest;
+    use warp::http::header::{HeaderMap, HeaderValue};
+
+    let body = br#"{"type":"Follow"}"#;
+    let with_header = |name: &'static str, value: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    };
+
+    assert!(verify_digest(&with_header("digest", digest_header_value(body).as_str()), body).is_ok());
+    assert!(verify_digest(&with_header("digest", digest_header_value(body).as_str()), br#"{"type":"Undo"}"#).is_err());
+    let sha512 = base64::encode(openssl::sha::sha512(body));
+    assert!(verify_digest(&with_header("digest", format!("sha-512={}", sha512).as_str()), body).is_ok());
+    // Every supported digest has to match.
+    assert!(verify_digest(&with_header("digest", format!("{},SHA-512=AAAA", digest_header_value(body)).as_str()), body).is_err());
+    // Unsupported algorithms don't verify anything.
+    assert!(verify_digest(&with_header("digest", "MD5=Q2hlY2sgSW50ZWdyaXR5IQ=="), body).is_err());
+    assert!(verify_digest(&HeaderMap::new(), body).is_err());
+
+    let sha256 = base64::encode(openssl::sha::sha256(body));
+    assert!(verify_digest(&with_header("content-digest", format!("sha-256=:{}:", sha256).as_str()), body).is_ok());
+    assert!(verify_digest(&with_header("content-digest", format!("sha-256=:{}:", sha512).as_str()), body).is_err());
+}
+
+#[test]
+fn test_verify_signature_freshness() {
+    use chrono::{Duration, TimeZone, Utc};
+    use commune::hancock::Signature;
+    use commune::handlers::apub::auth::verify_signature_freshness;
+    use warp::http::header::{HeaderMap, HeaderValue};
+
+    let now = Utc.ymd(2021, 3, 30).and_hms(12, 0, 0);
+    let max_skew = Duration::minutes(5);
+    let signature = |params: &str| {
+        Signature::parse(&HeaderValue::from_str(format!("keyId=\"https://test2.example.tld/users/a#main-key\",{},signature=\"AAAA\"", params).as_str()).unwrap()).unwrap()
+    };
+    let with_date = |date: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert("date", HeaderValue::from_str(date).unwrap());
+        headers
+    };
+    let signed = signature("headers=\"(request-target) host date digest\"");
+
+    assert!(verify_signature_freshness(&signed, &with_date("Tue, 30 Mar 2021 12:04:00 GMT"), max_skew, now).is_ok());
+    assert!(verify_signature_freshness(&signed, &with_date("Tue, 30 Mar 2021 11:56:00 GMT"), max_skew, now).is_ok());
+    assert!(verify_signature_freshness(&signed, &with_date("Tue, 30 Mar 2021 11:54:00 GMT"), max_skew, now).is_err());
+    assert!(verify_signature_freshness(&signed, &with_date("Tue, 30 Mar 2021 12:06:00 GMT"), max_skew, now).is_err());
+    assert!(verify_signature_freshness(&signed, &with_date("yesterday"), max_skew, now).is_err());
+    assert!(verify_signature_freshness(&signed, &HeaderMap::new(), max_skew, now).is_err());
+
+    // The request target, host and date have to be signed.
+    let headers = with_date("Tue, 30 Mar 2021 12:00:00 GMT");
+    assert!(verify_signature_freshness(&signature("headers=\"host date digest\""), &headers, max_skew, now).is_err());
+    assert!(verify_signature_freshness(&signature("headers=\"(request-target) date digest\""), &headers, max_skew, now).is_err());
+    assert!(verify_signature_freshness(&signature("headers=\"(request-target) host digest\""), &headers, max_skew, now).is_err());
+    assert!(verify_signature_freshness(&signature("created=1617105600"), &headers, max_skew, now).is_err());
+
+    let created = signature(format!("headers=\"(request-target) (created) host date\",created={}", now.timestamp()).as_str());
+    assert!(verify_signature_freshness(&created, &headers, max_skew, now).is_ok());
+    let stale = signature(format!("headers=\"(request-target) (created) host date\",created={}", now.timestamp() - 3600).as_str());
+    assert!(verify_signature_freshness(&stale, &headers, max_skew, now).is_err());
+}
+
+#[test]
+fn test_actor_public_keys() {
+    use commune::apub::actions::keys::key_document_owner;
+    use commune::apub::models::Actor;
+    use serde_json::json;
+
+    let actor = |public_key: serde_json::Value| {
+        serde_json::from_value::<Actor>(json!({
+            "type": "Person",
+            "id": "https://test2.example.tld/users/misaka4e21",
+            "inbox": "https://test2.example.tld/users/misaka4e21/inbox",
+            "outbox": "https://test2.example.tld/users/misaka4e21/outbox",
+            "preferredUsername": "misaka4e21",
+            "url": "https://test2.example.tld/@misaka4e21",
+            "publicKey": public_key,
+        }))
+        .unwrap()
+    };
+    let key = |key_id: &str, owner: &str| {
+        json!({"id": key_id, "owner": owner, "publicKeyPem": format!("PEM of {}", key_id)})
+    };
+    let owner = "https://test2.example.tld/users/misaka4e21";
+
+    let single = actor(key("https://test2.example.tld/users/misaka4e21/main-key", owner));
+    assert_eq!(
+        single.public_keys(),
+        vec![(String::from("https://test2.example.tld/users/misaka4e21/main-key"), String::from("PEM of https://test2.example.tld/users/misaka4e21/main-key"))]
+    );
+    assert_eq!(single.get_public_key_pem().as_deref(), Some("PEM of https://test2.example.tld/users/misaka4e21/main-key"));
+
+    // Keys owned by someone else are not keys of the actor.
+    let several = actor(json!([
+        key("https://test2.example.tld/keys/1", owner),
+        key("https://test2.example.tld/keys/2", "https://test2.example.tld/users/misaka4e22"),
+        key("https://test2.example.tld/keys/3", owner),
+    ]));
+    let key_ids: Vec<String> = several.public_keys().into_iter().map(|(key_id, _pem)| key_id).collect();
+    assert_eq!(key_ids, vec!["https://test2.example.tld/keys/1", "https://test2.example.tld/keys/3"]);
+    assert_eq!(actor(key("https://test2.example.tld/keys/2", "https://test2.example.tld/users/misaka4e22")).get_public_key_pem(), None);
+
+    // Nor are keys served by another host, even if they name the actor as their owner.
+    assert_eq!(actor(key("https://test3.example.tld/keys/1", owner)).public_keys(), vec![]);
+
+    assert_eq!(key_document_owner(&key("https://test2.example.tld/keys/1", owner)).as_deref(), Some(owner));
+    assert_eq!(key_document_owner(&serde_json::to_value(&single).unwrap()).as_deref(), Some(owner));
+    assert_eq!(key_document_owner(&json!({"id": "https://test2.example.tld/keys/1"})), None);
+}
+
+#[test]
+fn test_message_signature() {
+    use chrono::Utc;
+    use commune::apub::actions::delivery::sign_post_headers_rfc9421;
+    use commune::apub::rsa::generate_key_pair_pem;
+    use commune::hancock::MessageSignature;
+    use commune::handlers::apub::auth::{verify_message_signature_freshness, RequestSignature};
+    use warp::http::header::HeaderValue;
+    use warp::http::Method;
+
+    let keypair = generate_key_pair_pem().unwrap();
+    let uri = url::Url::parse("https://test1.example.tld/users/misaka4e22/inbox").unwrap();
+    let body = br#"{"type":"Follow"}"#;
+    let headers = sign_post_headers_rfc9421("https://test2.example.tld/users/misaka4e21#main-key", &keypair.private, &uri, body).unwrap();
+    assert_eq!(headers.get("content-digest").unwrap(), "sha-256=:GYwYnH3BiO6aICFt0ThC5bUIJ4byvqdpWtR8m5fNkww=:");
+
+    let signature = RequestSignature::from_headers(&headers).unwrap();
+    assert_eq!(signature.key_id(), Some("https://test2.example.tld/users/misaka4e21#main-key"));
+    assert!(signature.covers("content-digest"));
+    assert!(signature.verify(&Method::POST, uri.as_str(), &headers, &keypair.public));
+    assert!(!signature.verify(&Method::POST, "https://test1.example.tld/users/misaka4e21/inbox", &headers, &keypair.public));
+    assert!(!signature.verify(&Method::PUT, uri.as_str(), &headers, &keypair.public));
+    let mut tampered = headers.clone();
+    tampered.insert("content-digest", HeaderValue::from_static("sha-256=:AAAA:"));
+    assert!(!signature.verify(&Method::POST, uri.as_str(), &tampered, &keypair.public));
+    let other_keypair = generate_key_pair_pem().unwrap();
+    assert!(!signature.verify(&Method::POST, uri.as_str(), &headers, &other_keypair.public));
+
+    if let RequestSignature::MessageSignature(signature) = &signature {
+        assert!(verify_message_signature_freshness(signature, &headers, chrono::Duration::minutes(5), Utc::now()).is_ok());
+        assert!(verify_message_signature_freshness(signature, &headers, chrono::Duration::minutes(5), Utc::now() + chrono::Duration::hours(1)).is_err());
+    } else {
+        panic!("not an RFC 9421 signature");
+    }
+
+    // From RFC 9421, with a second signature; the first one in Signature-Input is the one used.
+    let signatures = MessageSignature::parse(
+        &HeaderValue::from_static(r#"sig-b21=();created=1618884473;keyid="test-key-rsa-pss";nonce="b3k2pp5k7z-50gnwp.yemd", sig2=("@method" "@authority" "@path" "content-digest");created=1618884480;keyid="test-key-rsa";alg="rsa-v1_5-sha256";expires=1618884540"#),
+        &HeaderValue::from_static("sig2=:AAAA:, sig-b21=:d2pmTvmbncD3xQm8E9ZV2828BjQWGgiwAaw5bAkgibUopemLJcWDy/lkbbHAve4cRAtx31Iq786U7it++wgGxbtRxf8Udx7zFZsckzXaJMkA7ChG52eSkFxykJeNqsrWH5S+oxNFlD4dzVuwe8DhTSja8xxbR/Z2cOGdCbzR72rgFWhzx2VjBqJzsPLMIQKhO4DGezXehhWwE56YCE+O6c0mKZsfxVrogUvA4HELjVKWmAvtl6UnCh8jYzuVG5WSb/QEVPnP5TmcAnLH1g+s++v6d4s8m0gCw1fV5/SITLq9mhho8K3+7EPYTU8IU1bLhdxO5Nyt8C8ssinQ98Xw9Q==:"),
+    )
+    .unwrap();
+    assert_eq!(signatures.len(), 2);
+    assert_eq!(signatures[0].label, "sig-b21");
+    assert!(signatures[0].components.is_empty());
+    assert_eq!(signatures[0].created, Some(1618884473));
+    assert_eq!(signatures[0].key_id.as_deref(), Some("test-key-rsa-pss"));
+    assert_eq!(signatures[1].components, vec!["@method", "@authority", "@path", "content-digest"]);
+    assert_eq!(signatures[1].algorithm.as_deref(), Some("rsa-v1_5-sha256"));
+    assert_eq!(signatures[1].expires, Some(1618884540));
+    assert_eq!(signatures[1].signature, vec![0, 0, 0]);
+
+    let mut headers = warp::http::header::HeaderMap::new();
+    headers.insert("content-digest", HeaderValue::from_static("sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:"));
+    assert_eq!(
+        String::from_utf8(signatures[1].signature_base(&Method::POST, "https://example.com/foo?param=Value&Pet=dog", &headers).unwrap()).unwrap(),
+        concat!(
+            "\"@method\": POST\n",
+            "\"@authority\": example.com\n",
+            "\"@path\": /foo\n",
+            "\"content-digest\": sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:\n",
+            "\"@signature-params\": (\"@method\" \"@authority\" \"@path\" \"content-digest\");created=1618884480;keyid=\"test-key-rsa\";alg=\"rsa-v1_5-sha256\";expires=1618884540",
+        )
+    );
+    // A covered header missing from the request can't be verified.
+    assert!(signatures[1].signature_base(&Method::POST, "https://example.com/foo", &warp::http::header::HeaderMap::new()).is_none());
+
+    assert!(MessageSignature::parse(&HeaderValue::from_static(r#"sig1=("@method";req)"#), &HeaderValue::from_static("sig1=:AAAA:")).is_err());
+    assert!(MessageSignature::parse(&HeaderValue::from_static(r#"sig1=("@method")"#), &HeaderValue::from_static("sig2=:AAAA:")).is_err());
+}
+
+#[test]
+fn test_ed25519_keys() {
+    use commune::apub::actions::delivery::{sign_post_headers_rfc9421, SigningKeys};
+    use commune::apub::ed25519::{generate_key_pair_pem, public_key_multibase, public_key_pem_from_multibase};
+    use commune::apub::models::Actor;
+    use commune::db::models::{Key, SIGNATURE_STYLE_CAVAGE, SIGNATURE_STYLE_RFC9421};
+    use commune::handlers::apub::auth::RequestSignature;
+    use warp::http::header::HeaderValue;
+    use warp::http::Method;
+
+    let keypair = generate_key_pair_pem().unwrap();
+    let multibase = public_key_multibase(&keypair.public).unwrap();
+    assert!(multibase.starts_with("z6Mk"));
+    assert_eq!(public_key_pem_from_multibase(&multibase).unwrap(), keypair.public);
+    assert!(public_key_multibase(&commune::apub::rsa::generate_key_pair_pem().unwrap().public).is_none());
+    assert!(public_key_pem_from_multibase("z6MkAAAA").is_none());
+
+    let uri = url::Url::parse("https://test1.example.tld/users/misaka4e22/inbox").unwrap();
+    let headers = sign_post_headers_rfc9421("https://test2.example.tld/users/misaka4e21#ed25519-key", &keypair.private, &uri, b"{}").unwrap();
+    let signature = RequestSignature::from_headers(&headers).unwrap();
+    assert!(headers.get("signature-input").unwrap().to_str().unwrap().contains(r#"alg="ed25519""#));
+    assert!(signature.verify(&Method::POST, uri.as_str(), &headers, &keypair.public));
+    assert!(!signature.verify(&Method::POST, uri.as_str(), &headers, &generate_key_pair_pem().unwrap().public));
+
+    // From RFC 9421.
+    let mut headers = warp::http::header::HeaderMap::new();
+    headers.insert("date", HeaderValue::from_static("Tue, 20 Apr 2021 02:07:55 GMT"));
+    headers.insert("content-type", HeaderValue::from_static("application/json"));
+    headers.insert("content-length", HeaderValue::from_static("18"));
+    headers.insert("signature-input", HeaderValue::from_static(r#"sig-b26=("date" "@method" "@path" "@authority" "content-type" "content-length");created=1618884473;keyid="test-key-ed25519""#));
+    headers.insert("signature", HeaderValue::from_static("sig-b26=:wqcAqbmYJ2ji2glfAMaRy4gruYYnx2nEFN2HN6jrnDnQCK1u02Gb04v9EDgwUPiu4A0w6vuQv5lIp5WPpBKRCw==:"));
+    let public_key_pem = "-----BEGIN PUBLIC KEY-----\nMCowBQYDK2VwAyEAJrQLj5P/89iXES9+vFgrIy29clF9CC/oPPsw3c5D0bs=\n-----END PUBLIC KEY-----\n";
+    let signature = RequestSignature::from_headers(&headers).unwrap();
+    assert!(signature.verify(&Method::POST, "https://example.com/foo?param=Value&Pet=dog", &headers, public_key_pem));
+
+    let actor: Actor = serde_json::from_value(serde_json::json!({
+        "type": "Person",
+        "id": "https://test2.example.tld/users/misaka4e21",
+        "inbox": "https://test2.example.tld/users/misaka4e21/inbox",
+        "outbox": "https://test2.example.tld/users/misaka4e21/outbox",
+        "preferredUsername": "misaka4e21",
+        "url": "https://test2.example.tld/@misaka4e21",
+        "publicKey": {
+            "id": "https://test2.example.tld/users/misaka4e21#main-key",
+            "owner": "https://test2.example.tld/users/misaka4e21",
+            "publicKeyPem": "PEM",
+        },
+        "assertionMethod": [
+            {
+                "id": "https://test2.example.tld/users/misaka4e21#ed25519-key",
+                "type": "Multikey",
+                "controller": "https://test2.example.tld/users/misaka4e21",
+                "publicKeyMultibase": multibase,
+            },
+            {
+                "id": "https://test2.example.tld/users/misaka4e22#ed25519-key",
+                "type": "Multikey",
+                "controller": "https://test2.example.tld/users/misaka4e22",
+                "publicKeyMultibase": multibase,
+            },
+        ],
+    }))
+    .unwrap();
+    assert_eq!(actor.public_keys(), vec![
+        (String::from("https://test2.example.tld/users/misaka4e21#main-key"), String::from("PEM")),
+        (String::from("https://test2.example.tld/users/misaka4e21#ed25519-key"), keypair.public.clone()),
+    ]);
+
+    // RFC 9421 signatures use the Ed25519 key when there is one, draft-cavage ones the RSA key.
+    let ed25519_key = Key {
+        id: 1,
+        uri: String::from("https://test2.example.tld/users/misaka4e21#ed25519-key"),
+        actor_id: 1,
+        public_key_pem: keypair.public.clone(),
+        created_at: chrono::Utc::now().naive_utc(),
+        private_key_pem: Some(keypair.private.clone()),
+    };
+    let mut keys = SigningKeys {
+        main_key_id: String::from("https://test2.example.tld/users/misaka4e21#main-key"),
+        main_private_key_pem: String::from("RSA PEM"),
+        ed25519_key: Some(ed25519_key),
+    };
+    assert_eq!(keys.for_style(SIGNATURE_STYLE_RFC9421), ("https://test2.example.tld/users/misaka4e21#ed25519-key", keypair.private.as_str()));
+    assert_eq!(keys.for_style(SIGNATURE_STYLE_CAVAGE), ("https://test2.example.tld/users/misaka4e21#main-key", "RSA PEM"));
+    keys.ed25519_key = None;
+    assert_eq!(keys.for_style(SIGNATURE_STYLE_RFC9421), ("https://test2.example.tld/users/misaka4e21#main-key", "RSA PEM"));
+}
+
+#[test]
+fn test_activity_reaction() {
+    use commune::apub::models::Activity;
+    use serde_json::json;
+
+    let activity = |value: serde_json::Value| serde_json::from_value::<Activity>(value).unwrap();
+    let emoji_react = activity(json!({
+        "type": "EmojiReact",
+        "id": "https://test2.example.tld/activities/1",
+        "actor": "https://test2.example.tld/users/misaka4e21",
+        "object": "https://test1.example.tld/posts/1",
+        "content": ":blobcat:",
+        "tag": [{
+            "type": "Emoji",
+            "name": ":blobcat:",
+            "icon": {"type": "Image", "url": "https://test2.example.tld/emojis/blobcat.png"},
+        }],
+    }));
+    assert_eq!(emoji_react.reaction_content(), Some(":blobcat:"));
+    assert_eq!(emoji_react.reaction_emoji_url().as_deref(), Some("https://test2.example.tld/emojis/blobcat.png"));
+
+    // Misskey sends reactions as Likes with content, and votes are Likes without.
+    let misskey_like = activity(json!({
+        "type": "Like",
+        "id": "https://test2.example.tld/likes/1",
+        "actor": "https://test2.example.tld/users/misaka4e21",
+        "object": "https://test1.example.tld/posts/1",
+        "content": "🍮",
+        "_misskey_reaction": "🍮",
+    }));
+    assert_eq!(misskey_like.reaction_content(), Some("🍮"));
+    assert_eq!(misskey_like.reaction_emoji_url(), None);
+    let vote = activity(json!({
+        "type": "Like",
+        "id": "https://test2.example.tld/likes/2",
+        "actor": "https://test2.example.tld/users/misaka4e21",
+        "object": "https://test1.example.tld/posts/1",
+    }));
+    assert_eq!(vote.reaction_content(), None);
+}
+
+#[test]
+fn test_activity_may_update() {
+    use commune::apub::models::Activity;
+    use serde_json::json;
+
+    let update = |object: serde_json::Value| serde_json::from_value::<Activity>(json!({
+        "type": "Update",
+        "id": "https://test2.example.tld/activities/1",
+        "actor": "https://test2.example.tld/users/misaka4e21",
+        "object": object,
+    })).unwrap();
+    let actor = |id: &str| json!({"type": "Person", "id": id});
+    let post = |author: &str| json!({"type": "Page", "id": "https://test2.example.tld/post/1", "attributedTo": author});
+
+    // An actor may only update itself.
+    let object = actor("https://test2.example.tld/users/misaka4e21");
+    assert!(update(object.clone()).may_update(&object));
+    let object = actor("https://test2.example.tld/users/misaka4e22");
+    assert!(!update(object.clone()).may_update(&object));
+
+    // And only edit its own posts.
+    let object = post("https://test2.example.tld/users/misaka4e21");
+    assert!(update(object.clone()).may_update(&object));
+    let object = post("https://test2.example.tld/users/misaka4e22");
+    assert!(!update(object.clone()).may_update(&object));
+    let object = json!({"type": "Page", "id": "https://test2.example.tld/post/1"});
+    assert!(!update(object.clone()).may_update(&object));
+}
+
+#[test]
+fn test_post_tombstone() {
+    use commune::apub::models::post_tombstone;
+    use commune::db::models::Post;
+
+    let published = chrono::NaiveDate::from_ymd(2021, 3, 22).and_hms(14, 9, 3);
+    let post = Post {
+        id: 1,
+        uri: String::from("https://test1.example.tld/posts/1"),
+        url: None,
+        kind: String::from("Page"),
+        author_id: 1,
+        community_id: 2,
+        in_reply_to_id: None,
+        thread_id: None,
+        title: Some(String::from("First")),
+        content: String::from("<p>Hello</p>"),
+        published,
+        updated_at: None,
+        deleted_at: None,
+        is_removed: true,
+        is_pinned: false,
+        upvotes: 0,
+        downvotes: 0,
+        shares: 0,
+    };
+    // A removed post doesn't say when it was deleted.
+    let tombstone = post_tombstone(&post);
+    assert_eq!(tombstone["formerType"], "Page");
+    assert!(tombstone.get("deleted").is_none());
+
+    let deleted = Post { deleted_at: Some(published), ..post };
+    assert_eq!(post_tombstone(&deleted)["deleted"], "2021-03-22T14:09:03Z");
+}
+
+#[test]
+fn test_activity_has_own_id() {
+    use commune::apub::models::Activity;
+    use serde_json::json;
+
+    let like = |id: &str| serde_json::from_value::<Activity>(json!({
+        "type": "Like",
+        "id": id,
+        "actor": "https://test2.example.tld/users/misaka4e21",
+        "object": "https://test1.example.tld/posts/1",
+    })).unwrap();
+    assert!(like("https://test2.example.tld/likes/1").has_own_id());
+    // An actor can't squat the predictable id of an activity on another server.
+    assert!(!like("https://test3.example.tld/likes/1").has_own_id());
+    assert!(!like("urn:uuid:4e21").has_own_id());
+}
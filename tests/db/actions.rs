@@ -1,4 +1,22 @@
 #[cfg(test)]
 mod actor;
 #[cfg(test)]
-mod follow;
\ No newline at end of file
+mod follow;
+#[cfg(test)]
+mod delivery;
+#[cfg(test)]
+mod activity;
+#[cfg(test)]
+mod post;
+#[cfg(test)]
+mod community;
+#[cfg(test)]
+mod moderation;
+#[cfg(test)]
+mod key;
+#[cfg(test)]
+mod vote;
+#[cfg(test)]
+mod reaction;
+#[cfg(test)]
+mod share;
\ No newline at end of file
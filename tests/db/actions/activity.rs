@@ -0,0 +1,64 @@
+use crate::fixtures::create_user_fixture;
+
+use diesel::Connection;
+use serde_json::json;
+
+use commune::apub::models::{Activity as ActivityS, PUBLIC};
+use commune::db::establish_connection;
+use commune::db::actions::activity::{
+    actor_count_outbox, actor_get_outbox, claim_activity, get_activity_by_uri, insert_activity,
+    prune_processed_activities, publish_activity, release_activity,
+};
+use commune::errors::{ActionResult, ActionError};
+
+#[test]
+fn test_actor_get_outbox() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let user_actor1 = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let user_actor2 = create_user_fixture(&conn, "misaka4e22", "test2.example.tld");
+
+    let mut public_activity = ActivityS::new_local("Announce", &user_actor1.actor, json!("https://test2.example.tld/posts/1"));
+    public_activity.to = Some(json!([PUBLIC]));
+    let public_activity = insert_activity(&conn, &user_actor1.actor, &public_activity)?;
+    assert!(public_activity.is_public);
+
+    let follow_activity = ActivityS::new_local("Follow", &user_actor1.actor, json!(user_actor2.actor.uri));
+    let follow_activity = publish_activity(&conn, &user_actor1.actor, std::slice::from_ref(&user_actor2.actor.inbox_uri), &follow_activity)?;
+    assert!(!follow_activity.is_public);
+    assert_eq!(get_activity_by_uri(&conn, follow_activity.uri.as_str())?, follow_activity);
+
+    // Only public activities are listed in the outbox.
+    assert_eq!(actor_get_outbox(&conn, &user_actor1.actor, 1)?, vec![public_activity]);
+    assert_eq!(actor_count_outbox(&conn, &user_actor1.actor)?, 1);
+    Ok(())
+}
+
+/// Forget the activities processed within the test transaction, so that only those of the test
+/// are pruned.
+fn clear_processed_activities(conn: &diesel::PgConnection) -> ActionResult<()> {
+    use diesel::prelude::*;
+    diesel::delete(commune::db::schema::processed_activities::table).execute(conn).map(|_n| ()).map_err(ActionError::from)
+}
+
+#[test]
+fn test_processed_activities() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    clear_processed_activities(&conn)?;
+    let uri = "https://test2.example.tld/activities/1";
+
+    assert!(claim_activity(&conn, uri)?);
+    // Only the first delivery gets to apply it.
+    assert!(!claim_activity(&conn, uri)?);
+    // Unless it failed to apply.
+    release_activity(&conn, uri)?;
+    assert!(claim_activity(&conn, uri)?);
+
+    let an_hour_ago = (chrono::Utc::now() - chrono::Duration::hours(1)).naive_utc();
+    assert_eq!(prune_processed_activities(&conn, an_hour_ago)?, 0);
+    assert!(!claim_activity(&conn, uri)?);
+    assert_eq!(prune_processed_activities(&conn, chrono::Utc::now().naive_utc())?, 1);
+    assert!(claim_activity(&conn, uri)?);
+    Ok(())
+}
@@ -54,4 +54,151 @@ fn test_insert_new_actor() -> ActionResult<()> {
     let actor_get = actor::get_actor_by_uri(&conn, "https://test2.example.tld/users/misaka4e23")?;    
     assert_eq!(actor, actor_get);
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_local_recipients() -> ActionResult<()> {
+    use commune::apub::actions::inbox::local_recipients;
+    use commune::db::actions::follow::follow_actor_by_uri;
+    use commune::db::models::Actor;
+    use std::collections::HashSet;
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let local_domains: HashSet<String> = vec![String::from("test1.example.tld")].into_iter().collect();
+    let user_actor1 = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let user_actor2 = create_user_fixture(&conn, "misaka4e22", "test1.example.tld");
+    let remote_actor = create_user_fixture(&conn, "misaka4e23", "test2.example.tld");
+    let remote_follower = create_user_fixture(&conn, "misaka4e24", "test3.example.tld");
+    for follower in &[&user_actor2, &remote_follower] {
+        follow_actor_by_uri(&conn, follower.actor.uri.as_str(), remote_actor.actor.uri.as_str(), None)?;
+    }
+    let followers_uri = remote_actor.actor.followers_uri.clone().unwrap();
+    assert_eq!(actor::get_actor_by_followers_uri(&conn, followers_uri.as_str())?, remote_actor.actor);
+
+    let addressees = vec![
+        String::from("https://www.w3.org/ns/activitystreams#Public"),
+        user_actor1.actor.uri.clone(),
+        followers_uri,
+        user_actor2.actor.uri.clone(),
+        String::from("https://test1.example.tld/users/nobody"),
+    ];
+    // Local followers of the remote actor only, each once.
+    assert_eq!(
+        local_recipients(&conn, &local_domains, &addressees)?,
+        vec![user_actor1.actor, user_actor2.actor]
+    );
+
+    // An actor of another host can't claim the followers collection.
+    let set_followers_uri = |actor: &Actor, collection_uri: &str| -> ActionResult<Actor> {
+        use commune::db::schema::actors::dsl::*;
+        use diesel::prelude::*;
+        diesel::update(actor).set(followers_uri.eq(Some(collection_uri))).get_result(&conn).map_err(ActionError::from)
+    };
+    let followers_uri = remote_actor.actor.followers_uri.clone().unwrap();
+    set_followers_uri(&remote_follower.actor, followers_uri.as_str())?;
+    assert_eq!(actor::get_actor_by_followers_uri(&conn, followers_uri.as_str())?, remote_actor.actor);
+    // And if another actor of the same host does, neither is trusted with it.
+    let remote_actor2 = create_user_fixture(&conn, "misaka4e25", "test2.example.tld");
+    set_followers_uri(&remote_actor2.actor, followers_uri.as_str())?;
+    assert!(matches!(actor::get_actor_by_followers_uri(&conn, followers_uri.as_str()), Err(ActionError::InvalidForm)));
+    Ok(())
+}
+
+#[test]
+fn test_update_actor() -> ActionResult<()> {
+    use commune::db::models::{ActorType, NewActor, NewLocalActorBuilder};
+
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let user_actor = create_user_fixture(&conn, "misaka4e22", "test2.example.tld");
+    let new_actor = NewActor {
+        name: Some(String::from("Misaka 4e22")),
+        public_key_pem: String::from("ROTATED_KEY"),
+        shared_inbox_uri: None,
+        ..NewLocalActorBuilder {
+            username: "misaka4e22",
+            domain: "test2.example.tld",
+            lang: "und",
+            actor_type: ActorType::Person,
+            public_key_pem: "TEST_CERT",
+        }.build()
+    };
+
+    let actor = actor::update_actor(&conn, &user_actor.actor, &new_actor)?;
+    assert_eq!(actor.id, user_actor.actor.id);
+    assert_eq!(actor.uri, user_actor.actor.uri);
+    assert_eq!(actor.name, "Misaka 4e22");
+    assert_eq!(actor.public_key_pem, "ROTATED_KEY");
+    assert_eq!(actor.shared_inbox_uri, None);
+    assert!(actor.updated_at > user_actor.actor.updated_at);
+    assert_eq!(actor::get_actor_by_uri(&conn, actor.uri.as_str())?, actor);
+    Ok(())
+}
+
+#[test]
+fn test_get_stale_remote_actors() -> ActionResult<()> {
+    use chrono::{Duration, Utc};
+    use commune::apub::actions::keys::can_refresh_actor;
+    use std::collections::HashSet;
+
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let local_actor = create_user_fixture(&conn, "misaka4e21", "test1.example.tld").actor;
+    let remote_actor = create_user_fixture(&conn, "misaka4e22", "test2.example.tld").actor;
+    let local_domains: HashSet<String> = vec![String::from("test1.example.tld")].into_iter().collect();
+    let now = Utc::now();
+
+    // Local actors are never stale, and remote ones only once not refreshed for a while.
+    let stale = actor::get_stale_remote_actors(&conn, &local_domains, (now + Duration::hours(1)).naive_utc(), 1000)?;
+    assert!(stale.contains(&remote_actor));
+    assert!(!stale.contains(&local_actor));
+    let stale = actor::get_stale_remote_actors(&conn, &local_domains, (now - Duration::hours(1)).naive_utc(), 1000)?;
+    assert!(!stale.contains(&remote_actor));
+
+    assert!(!can_refresh_actor(&local_domains, &local_actor, now + Duration::hours(1)));
+    assert!(!can_refresh_actor(&local_domains, &remote_actor, now));
+    assert!(can_refresh_actor(&local_domains, &remote_actor, now + Duration::hours(1)));
+    let touched = actor::touch_actor(&conn, &remote_actor)?;
+    assert!(touched.updated_at > remote_actor.updated_at);
+    Ok(())
+}
+
+#[test]
+fn test_delete_actor() -> ActionResult<()> {
+    use commune::db::actions::follow::{actor_count_followers, actor_count_following, follow_actor_by_uri};
+    use commune::db::actions::post::get_post_by_id;
+    use commune::db::actions::reaction::{add_reaction, post_count_reactions};
+    use commune::db::actions::share::add_share;
+    use commune::db::actions::vote::set_vote;
+    use commune::db::models::{VOTE_DOWN, VOTE_UP};
+    use crate::fixtures::{create_group_fixture, create_thread_fixture};
+
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let local_actor = create_user_fixture(&conn, "misaka4e21", "test1.example.tld").actor;
+    let remote_actor = create_user_fixture(&conn, "misaka4e22", "test2.example.tld").actor;
+    let community = create_group_fixture(&conn, "railgun", "test1.example.tld");
+    follow_actor_by_uri(&conn, &local_actor.uri, &remote_actor.uri, None)?;
+    follow_actor_by_uri(&conn, &remote_actor.uri, &local_actor.uri, None)?;
+    let thread = create_thread_fixture(&conn, &remote_actor, &community, "https://test2.example.tld/posts/1", 0);
+    let local_thread = create_thread_fixture(&conn, &local_actor, &community, "https://test1.example.tld/posts/1", 0);
+    set_vote(&conn, &remote_actor, &local_thread, VOTE_DOWN, Some("https://test2.example.tld/dislikes/1"))?;
+    set_vote(&conn, &local_actor, &local_thread, VOTE_UP, None)?;
+    add_reaction(&conn, &remote_actor, &local_thread, "👍", None, Some("https://test2.example.tld/reacts/1"))?;
+    add_share(&conn, &remote_actor, &local_thread, Some("https://test2.example.tld/announces/1"))?;
+
+    let deleted = actor::delete_actor(&conn, &remote_actor)?;
+    assert!(deleted.is_deleted());
+    assert_eq!(deleted.uri, remote_actor.uri);
+    assert_eq!(actor_count_followers(&conn, &local_actor)?, 0);
+    assert_eq!(actor_count_following(&conn, &local_actor)?, 0);
+    let thread = get_post_by_id(&conn, thread.id)?;
+    assert!(thread.is_deleted());
+    assert_eq!(thread.title, None);
+    assert_eq!(thread.content, "");
+    // Votes, reactions and shares of the deleted actor no longer count.
+    let local_thread = get_post_by_id(&conn, local_thread.id)?;
+    assert_eq!((local_thread.upvotes, local_thread.downvotes, local_thread.shares), (1, 0, 0));
+    assert_eq!(post_count_reactions(&conn, &local_thread)?, vec![]);
+    Ok(())
+}
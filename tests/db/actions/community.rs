@@ -0,0 +1,116 @@
+use crate::fixtures::{create_community_fixture, create_group_fixture, create_user_fixture};
+
+use diesel::prelude::*;
+use serde_json::json;
+use std::collections::HashSet;
+
+use commune::apub::actions::community::announce_to_followers;
+use commune::apub::models::Activity;
+use commune::db::establish_connection;
+use commune::db::actions::follow::follow_actor_by_uri;
+use commune::db::models::Delivery;
+use commune::db::actions::actor::{get_local_actor_with_key, get_person_by_username_domain};
+use commune::db::actions::community::{create_community, get_community_actor_by_name_domain, get_community_by_name_domain};
+use commune::errors::{ActionResult, ActionError};
+
+#[test]
+fn test_create_community() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let owner = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let community_actor = create_community(&conn, "railgun", "test1.example.tld", "Railgun", "Judgement 177", "und", &owner)?;
+
+    assert_eq!(community_actor.actor.kind, "Group");
+    assert_eq!(community_actor.actor.uri, "https://test1.example.tld/communities/railgun");
+    assert_eq!(community_actor.actor.name, "Railgun");
+    assert_eq!(community_actor.actor.summary, "Judgement 177");
+    assert_eq!(community_actor.actor.following_uri, None);
+    assert_eq!(community_actor.community.owner_id, owner.actor.id);
+    assert_eq!(get_community_actor_by_name_domain(&conn, "railgun", "test1.example.tld")?, community_actor);
+
+    // Names are shared with users.
+    assert!(matches!(
+        create_community(&conn, "misaka4e21", "test1.example.tld", "", "", "und", &owner),
+        Err(ActionError::InsertError)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_community_and_person_lookups() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let owner = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let community_actor = create_community_fixture(&conn, "railgun", "test1.example.tld", &owner);
+
+    assert_eq!(get_community_by_name_domain(&conn, "railgun", "test1.example.tld")?, community_actor.actor);
+    assert!(matches!(
+        get_community_by_name_domain(&conn, "misaka4e21", "test1.example.tld"),
+        Err(ActionError::NotFound)
+    ));
+    assert_eq!(get_person_by_username_domain(&conn, "misaka4e21", "test1.example.tld")?, owner.actor);
+    assert!(matches!(
+        get_person_by_username_domain(&conn, "railgun", "test1.example.tld"),
+        Err(ActionError::NotFound)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_get_local_actor_with_key() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let owner = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let community_actor = create_community_fixture(&conn, "railgun", "test1.example.tld", &owner);
+
+    assert_eq!(
+        get_local_actor_with_key(&conn, owner.actor.id)?,
+        (owner.actor.clone(), owner.user.private_key_pem.clone())
+    );
+    assert_eq!(
+        get_local_actor_with_key(&conn, community_actor.actor.id)?,
+        (community_actor.actor.clone(), community_actor.community.private_key_pem.clone())
+    );
+    Ok(())
+}
+
+#[test]
+fn test_announce_to_followers() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let local_domains: HashSet<String> = vec![String::from("test1.example.tld")].into_iter().collect();
+    let owner = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let community = create_community_fixture(&conn, "railgun", "test1.example.tld", &owner).actor;
+    let author = create_user_fixture(&conn, "misaka4e22", "test2.example.tld");
+    for (username, domain) in &[("misaka4e22", "test2.example.tld"), ("misaka4e23", "test2.example.tld"), ("misaka4e24", "test3.example.tld")] {
+        let follower = if *username == "misaka4e22" { author.clone() } else { create_user_fixture(&conn, username, domain) };
+        follow_actor_by_uri(&conn, follower.actor.uri.as_str(), community.uri.as_str(), None)?;
+    }
+
+    let create = Activity::new_local("Create", &author.actor, json!({
+        "type": "Page",
+        "id": "https://test2.example.tld/posts/1",
+        "attributedTo": author.actor.uri,
+    }));
+    let announce = announce_to_followers(&conn, &local_domains, &community, &create)?.expect("local community");
+    assert_eq!(announce.kind, "Announce");
+    assert_eq!(announce.actor, community.uri);
+    assert_eq!(announce.object["id"], json!(create.id));
+    assert_eq!(announce.object["object"]["id"], json!("https://test2.example.tld/posts/1"));
+
+    // One delivery per shared inbox, none to the local owner.
+    let mut inboxes = {
+        use commune::db::schema::deliveries::dsl::*;
+        deliveries.filter(actor_id.eq(community.id)).load::<Delivery>(&conn)?
+    }
+    .into_iter()
+    .map(|delivery| delivery.inbox_uri)
+    .collect::<Vec<String>>();
+    inboxes.sort();
+    assert_eq!(inboxes, vec!["https://test2.example.tld/inbox", "https://test3.example.tld/inbox"]);
+
+    // Remote communities relay their posts themselves.
+    let remote_community = create_group_fixture(&conn, "index", "test2.example.tld");
+    assert!(announce_to_followers(&conn, &local_domains, &remote_community, &create)?.is_none());
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use crate::fixtures::create_user_fixture;
+
+use chrono::Duration;
+use diesel::Connection;
+
+use commune::db::establish_connection;
+use commune::apub::models::Activity;
+use commune::db::actions::delivery::{
+    claim_due_delivery, enqueue_activity, enqueue_delivery, forget_signature_style, get_signature_style, mark_delivered,
+    mark_delivery_failed, set_signature_style,
+};
+use commune::db::models::{DELIVERY_DELIVERED, DELIVERY_FAILED, DELIVERY_PENDING, SIGNATURE_STYLE_CAVAGE, SIGNATURE_STYLE_RFC9421};
+use commune::errors::{ActionResult, ActionError};
+
+/// Empty the queue within the test transaction, so that only deliveries of the test are due.
+fn clear_deliveries(conn: &diesel::PgConnection) -> ActionResult<()> {
+    use diesel::prelude::*;
+    diesel::delete(commune::db::schema::deliveries::table).execute(conn).map(|_n| ()).map_err(ActionError::from)
+}
+
+#[test]
+fn test_claim_due_delivery() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    clear_deliveries(&conn)?;
+    let user_actor = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let delivery = enqueue_delivery(&conn, user_actor.actor.id, "https://test2.example.tld/inbox", "{}")?;
+    assert_eq!(delivery.status, DELIVERY_PENDING);
+
+    let claimed = claim_due_delivery(&conn, Duration::minutes(5))?.ok_or(ActionError::NotFound)?;
+    assert_eq!(claimed.id, delivery.id);
+    // Leased deliveries are not handed out twice.
+    assert_eq!(claim_due_delivery(&conn, Duration::minutes(5))?, None);
+
+    let delivered = mark_delivered(&conn, &claimed, 202)?;
+    assert_eq!(delivered.status, DELIVERY_DELIVERED);
+    assert_eq!(delivered.attempts, 1);
+    assert_eq!(delivered.last_status_code, Some(202));
+    Ok(())
+}
+
+#[test]
+fn test_mark_delivery_failed() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    clear_deliveries(&conn)?;
+    let user_actor = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let delivery = enqueue_delivery(&conn, user_actor.actor.id, "https://test2.example.tld/inbox", "{}")?;
+
+    let retried = mark_delivery_failed(&conn, &delivery, Some(503), "503 Service Unavailable", false, Duration::hours(48))?;
+    assert_eq!(retried.status, DELIVERY_PENDING);
+    assert_eq!(retried.last_status_code, Some(503));
+    assert!(retried.next_attempt_at > delivery.next_attempt_at);
+    assert_eq!(claim_due_delivery(&conn, Duration::minutes(5))?, None);
+
+    let given_up = mark_delivery_failed(&conn, &retried, None, "fetch error", false, Duration::seconds(0))?;
+    assert_eq!(given_up.status, DELIVERY_FAILED);
+    assert_eq!(given_up.attempts, 2);
+    Ok(())
+}
+
+#[test]
+fn test_enqueue_activity_once_per_inbox() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    clear_deliveries(&conn)?;
+    let user_actor = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let activity = Activity::new_local("Follow", &user_actor.actor, serde_json::json!("https://test2.example.tld/users/misaka4e22"));
+    let inbox_uris = vec![
+        String::from("https://test2.example.tld/inbox"),
+        String::from("https://test3.example.tld/inbox"),
+        String::from("https://test2.example.tld/inbox"),
+    ];
+    let deliveries = enqueue_activity(&conn, &user_actor.actor, &inbox_uris, &activity)?;
+    assert_eq!(
+        deliveries.iter().map(|delivery| delivery.inbox_uri.as_str()).collect::<Vec<&str>>(),
+        vec!["https://test2.example.tld/inbox", "https://test3.example.tld/inbox"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_signature_style() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let an_hour_ago = (chrono::Utc::now() - Duration::hours(1)).naive_utc();
+
+    assert_eq!(get_signature_style(&conn, "test2.example.tld", an_hour_ago)?, None);
+    set_signature_style(&conn, "test2.example.tld", SIGNATURE_STYLE_RFC9421)?;
+    assert_eq!(get_signature_style(&conn, "test2.example.tld", an_hour_ago)?.as_deref(), Some(SIGNATURE_STYLE_RFC9421));
+    set_signature_style(&conn, "test2.example.tld", SIGNATURE_STYLE_CAVAGE)?;
+    assert_eq!(get_signature_style(&conn, "test2.example.tld", an_hour_ago)?.as_deref(), Some(SIGNATURE_STYLE_CAVAGE));
+
+    // Styles learnt too long ago are forgotten.
+    let in_an_hour = (chrono::Utc::now() + Duration::hours(1)).naive_utc();
+    assert_eq!(get_signature_style(&conn, "test2.example.tld", in_an_hour)?, None);
+    // As are styles the host rejected.
+    forget_signature_style(&conn, "test2.example.tld")?;
+    assert_eq!(get_signature_style(&conn, "test2.example.tld", an_hour_ago)?, None);
+    Ok(())
+}
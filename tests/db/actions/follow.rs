@@ -1,9 +1,10 @@
-use crate::fixtures::create_user_fixture;
+use crate::fixtures::{create_group_fixture, create_user_fixture};
 
 use diesel::Connection;
 
 use commune::db::establish_connection;
-use commune::db::actions::follow::{follow_actor_by_uri, actor_get_followers};
+use commune::db::actions::follow::{follow_actor_by_uri, actor_get_followers, actor_get_pending_followers, approve_follow, reject_follow, request_follow, get_follow_by_uri};
+use commune::db::models::{Actor, FOLLOW_FOLLOWER, FOLLOW_PENDING};
 use commune::errors::{ActionResult, ActionError};
 
 #[test]
@@ -12,7 +13,7 @@ fn test_follow_actor_by_uri() -> ActionResult<()> {
     conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
     let user_actor1 = create_user_fixture(&conn, "misaka4e21", "test2.example.tld");
     let user_actor2 = create_user_fixture(&conn, "misaka4e22", "test1.example.tld");
-    let follow = follow_actor_by_uri(&conn, "https://test2.example.tld/users/misaka4e21", "https://test1.example.tld/users/misaka4e22")?;
+    let follow = follow_actor_by_uri(&conn, "https://test2.example.tld/users/misaka4e21", "https://test1.example.tld/users/misaka4e22", None)?;
     assert_eq!(follow.follower_id, user_actor1.actor.id);
     assert_eq!(follow.following_id, user_actor2.actor.id);
     Ok(())
@@ -24,10 +25,123 @@ fn test_actor_get_followers() -> ActionResult<()> {
     conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
     let user_actor1 = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
     let user_actor2 = create_user_fixture(&conn, "misaka4e22", "test1.example.tld");
-    let _follow = follow_actor_by_uri(&conn, "https://test1.example.tld/users/misaka4e21", "https://test1.example.tld/users/misaka4e22")?;
+    let _follow = follow_actor_by_uri(&conn, "https://test1.example.tld/users/misaka4e21", "https://test1.example.tld/users/misaka4e22", None)?;
 
     let followers_vec = actor_get_followers(&conn, &user_actor2.actor, 1)?;
     assert_eq!(followers_vec[0], user_actor1.actor);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_follow_actor_by_uri_again() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let _user_actor1 = create_user_fixture(&conn, "misaka4e21", "test2.example.tld");
+    let _user_actor2 = create_user_fixture(&conn, "misaka4e22", "test1.example.tld");
+    let follow = follow_actor_by_uri(&conn, "https://test2.example.tld/users/misaka4e21", "https://test1.example.tld/users/misaka4e22", Some("https://test2.example.tld/activities/1"))?;
+    assert_eq!(follow.uri.as_deref(), Some("https://test2.example.tld/activities/1"));
+    let follow_again = follow_actor_by_uri(&conn, "https://test2.example.tld/users/misaka4e21", "https://test1.example.tld/users/misaka4e22", Some("https://test2.example.tld/activities/2"))?;
+    assert_eq!(follow_again.uri.as_deref(), Some("https://test2.example.tld/activities/2"));
+    assert_eq!(follow_again.created_at, follow.created_at);
+    Ok(())
+}
+
+
+fn lock_actor(conn: &diesel::PgConnection, actor: &Actor) -> ActionResult<Actor> {
+    use diesel::prelude::*;
+    use commune::db::schema::actors::dsl::*;
+    diesel::update(actor).set(is_locked.eq(true)).get_result(conn).map_err(ActionError::from)
+}
+
+#[test]
+fn test_approve_follow() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let user_actor1 = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let user_actor2 = create_user_fixture(&conn, "misaka4e22", "test1.example.tld");
+    let locked_actor = lock_actor(&conn, &user_actor2.actor)?;
+    let _follow = follow_actor_by_uri(&conn, "https://test1.example.tld/users/misaka4e21", "https://test1.example.tld/users/misaka4e22", None)?;
+
+    assert!(actor_get_followers(&conn, &locked_actor, 1)?.is_empty());
+    assert_eq!(actor_get_pending_followers(&conn, &locked_actor, 1)?, vec![user_actor1.actor.clone()]);
+
+    let follow = approve_follow(&conn, &user_actor1.actor, &locked_actor)?;
+    assert_eq!(follow.role, FOLLOW_FOLLOWER);
+    assert!(actor_get_pending_followers(&conn, &locked_actor, 1)?.is_empty());
+    assert_eq!(actor_get_followers(&conn, &locked_actor, 1)?, vec![user_actor1.actor.clone()]);
+    // Approved followers are no longer pending.
+    assert!(matches!(reject_follow(&conn, &user_actor1.actor, &locked_actor), Err(ActionError::NotFound)));
+    Ok(())
+}
+
+#[test]
+fn test_reject_follow() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let user_actor1 = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let user_actor2 = create_user_fixture(&conn, "misaka4e22", "test1.example.tld");
+    let locked_actor = lock_actor(&conn, &user_actor2.actor)?;
+    let _follow = follow_actor_by_uri(&conn, "https://test1.example.tld/users/misaka4e21", "https://test1.example.tld/users/misaka4e22", None)?;
+
+    let follow = reject_follow(&conn, &user_actor1.actor, &locked_actor)?;
+    assert_eq!(follow.follower_id, user_actor1.actor.id);
+    assert!(actor_get_pending_followers(&conn, &locked_actor, 1)?.is_empty());
+    assert!(actor_get_followers(&conn, &locked_actor, 1)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_request_follow() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let user_actor1 = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let user_actor2 = create_user_fixture(&conn, "misaka4e22", "test2.example.tld");
+    // Follows of unlocked remote actors are pending as well, until the remote server accepts them.
+    let follow = request_follow(&conn, &user_actor1.actor, &user_actor2.actor, "https://test1.example.tld/activities/1")?;
+    assert_eq!(follow.role, FOLLOW_PENDING);
+    assert!(actor_get_followers(&conn, &user_actor2.actor, 1)?.is_empty());
+
+    let follow_get = get_follow_by_uri(&conn, "https://test1.example.tld/activities/1")?;
+    assert_eq!(follow_get, follow);
+    Ok(())
+}
+
+#[test]
+fn test_actor_get_following() -> ActionResult<()> {
+    use commune::db::actions::follow::{actor_count_following, actor_get_following};
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let user_actor1 = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let user_actor2 = create_user_fixture(&conn, "misaka4e22", "test1.example.tld");
+    let user_actor3 = create_user_fixture(&conn, "misaka4e23", "test2.example.tld");
+    let _follow = follow_actor_by_uri(&conn, "https://test1.example.tld/users/misaka4e21", "https://test1.example.tld/users/misaka4e22", None)?;
+    let _follow = request_follow(&conn, &user_actor1.actor, &user_actor3.actor, "https://test1.example.tld/activities/1")?;
+
+    // Pending follows are not listed.
+    let following_vec = actor_get_following(&conn, &user_actor1.actor, 1)?;
+    assert_eq!(following_vec, vec![user_actor2.actor.clone()]);
+    assert_eq!(actor_count_following(&conn, &user_actor1.actor)?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_actor_has_local_followers() -> ActionResult<()> {
+    use commune::db::actions::follow::actor_has_local_followers;
+    use std::collections::HashSet;
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let local_domains: HashSet<String> = vec![String::from("test1.example.tld")].into_iter().collect();
+    let user_actor1 = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let _user_actor2 = create_user_fixture(&conn, "misaka4e22", "test3.example.tld");
+    let community = create_group_fixture(&conn, "railgun", "test2.example.tld");
+
+    // Remote followers and pending follows don't count.
+    let _follow = follow_actor_by_uri(&conn, "https://test3.example.tld/users/misaka4e22", "https://test2.example.tld/communities/railgun", None)?;
+    let _follow = request_follow(&conn, &user_actor1.actor, &community, "https://test1.example.tld/activities/1")?;
+    assert!(!actor_has_local_followers(&conn, &local_domains, &community)?);
+
+    approve_follow(&conn, &user_actor1.actor, &community)?;
+    assert!(actor_has_local_followers(&conn, &local_domains, &community)?);
+    Ok(())
+}
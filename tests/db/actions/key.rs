@@ -0,0 +1,124 @@
+use crate::fixtures::{create_community_fixture, create_user_fixture};
+
+use diesel::Connection;
+
+use commune::apub::ed25519;
+use commune::apub::models::{ed25519_key_id, main_key_id};
+use commune::db::establish_connection;
+use commune::db::actions::key::{actor_get_keys, create_missing_local_ed25519_keys, get_key_by_uri, get_local_ed25519_key, get_or_create_local_ed25519_key, set_actor_keys};
+use commune::errors::{ActionResult, ActionError};
+
+#[test]
+fn test_local_actor_keys() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let user_actor = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let community_actor = create_community_fixture(&conn, "railgun", "test1.example.tld", &user_actor);
+
+    for actor in &[user_actor.actor, community_actor.actor] {
+        let key = get_key_by_uri(&conn, main_key_id(actor.uri.as_str()).as_str())?;
+        assert_eq!(key.actor_id, actor.id);
+        assert_eq!(key.public_key_pem, actor.public_key_pem);
+        assert_eq!(key.private_key_pem, None);
+        let ed25519_key = get_key_by_uri(&conn, ed25519_key_id(actor.uri.as_str()).as_str())?;
+        assert_eq!(ed25519_key.actor_id, actor.id);
+        assert!(ed25519::public_key_multibase(ed25519_key.public_key_pem.as_str()).is_some());
+        assert!(ed25519_key.private_key_pem.is_some());
+        assert_eq!(get_or_create_local_ed25519_key(&conn, actor)?, ed25519_key);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_create_missing_ed25519_key() -> ActionResult<()> {
+    use commune::db::schema::keys;
+    use diesel::prelude::*;
+
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let actor = create_user_fixture(&conn, "misaka4e21", "test1.example.tld").actor;
+    // As for actors created before they had Ed25519 keys.
+    let key_id = ed25519_key_id(actor.uri.as_str());
+    diesel::delete(keys::table.filter(keys::uri.eq(&key_id))).execute(&conn).map_err(ActionError::from)?;
+    // Looking the key up doesn't create it.
+    assert!(matches!(get_local_ed25519_key(&conn, &actor), Err(ActionError::NotFound)));
+
+    let key = get_or_create_local_ed25519_key(&conn, &actor)?;
+    assert_eq!(get_local_ed25519_key(&conn, &actor)?, key);
+    assert_eq!(key.uri, key_id);
+    assert_eq!(key.actor_id, actor.id);
+    assert!(ed25519::public_key_multibase(key.public_key_pem.as_str()).is_some());
+    assert!(key.private_key_pem.is_some());
+    assert_eq!(get_or_create_local_ed25519_key(&conn, &actor)?, key);
+    Ok(())
+}
+
+#[test]
+fn test_create_missing_local_ed25519_keys() -> ActionResult<()> {
+    use commune::db::schema::keys;
+    use diesel::prelude::*;
+
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let user_actor = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let community = create_community_fixture(&conn, "railgun", "test1.example.tld", &user_actor).actor;
+    let other_user = create_user_fixture(&conn, "misaka4e22", "test1.example.tld").actor;
+    // As for actors created before they had Ed25519 keys.
+    let key_ids = vec![ed25519_key_id(user_actor.actor.uri.as_str()), ed25519_key_id(community.uri.as_str())];
+    diesel::delete(keys::table.filter(keys::uri.eq_any(&key_ids))).execute(&conn).map_err(ActionError::from)?;
+    let other_key = get_key_by_uri(&conn, ed25519_key_id(other_user.uri.as_str()).as_str())?;
+
+    let created = create_missing_local_ed25519_keys(&conn)?;
+    assert_eq!(created.iter().map(|key| key.uri.clone()).collect::<Vec<String>>(), key_ids);
+    assert_eq!(created[0].actor_id, user_actor.actor.id);
+    assert_eq!(created[1].actor_id, community.id);
+    assert!(created.iter().all(|key| key.private_key_pem.is_some()));
+    // Existing keys are kept.
+    assert_eq!(get_key_by_uri(&conn, other_key.uri.as_str())?, other_key);
+    assert_eq!(create_missing_local_ed25519_keys(&conn)?, vec![]);
+    Ok(())
+}
+
+#[test]
+fn test_set_actor_keys() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let actor1 = create_user_fixture(&conn, "misaka4e21", "test2.example.tld").actor;
+    let actor2 = create_user_fixture(&conn, "misaka4e22", "test2.example.tld").actor;
+    let key = |key_id: &str, pem: &str| (String::from(key_id), String::from(pem));
+
+    let keys = set_actor_keys(&conn, &actor1, &[
+        key("https://test2.example.tld/keys/1", "PEM1"),
+        key("https://test2.example.tld/keys/2", "PEM2"),
+    ])?;
+    let key_ids: Vec<&str> = keys.iter().map(|key| key.uri.as_str()).collect();
+    assert_eq!(key_ids, vec!["https://test2.example.tld/keys/1", "https://test2.example.tld/keys/2"]);
+
+    // The main key is gone, the first key rotated.
+    let keys = set_actor_keys(&conn, &actor1, &[
+        key("https://test2.example.tld/keys/1", "PEM1'"),
+        key("https://test2.example.tld/keys/2", "PEM2"),
+    ])?;
+    assert_eq!(keys[0].public_key_pem, "PEM1'");
+    assert!(get_key_by_uri(&conn, main_key_id(actor1.uri.as_str()).as_str()).is_err());
+
+    // A key listed by another actor now belongs to it.
+    set_actor_keys(&conn, &actor2, &[key("https://test2.example.tld/keys/2", "PEM2")])?;
+    assert_eq!(get_key_by_uri(&conn, "https://test2.example.tld/keys/2")?.actor_id, actor2.id);
+    assert_eq!(actor_get_keys(&conn, &actor1)?.len(), 1);
+
+    // Keys of another host can't be claimed, even if held by nobody yet.
+    let actor3 = create_user_fixture(&conn, "misaka4e23", "test3.example.tld").actor;
+    set_actor_keys(&conn, &actor3, &[key("https://test3.example.tld/keys/3", "PEM3")])?;
+    let keys = set_actor_keys(&conn, &actor2, &[
+        key("https://test2.example.tld/keys/2", "PEM2"),
+        key("https://test3.example.tld/keys/3", "PEM3'"),
+        key("https://test3.example.tld/keys/4", "PEM4"),
+    ])?;
+    assert_eq!(keys.len(), 1);
+    let key3 = get_key_by_uri(&conn, "https://test3.example.tld/keys/3")?;
+    assert_eq!(key3.actor_id, actor3.id);
+    assert_eq!(key3.public_key_pem, "PEM3");
+    assert!(matches!(get_key_by_uri(&conn, "https://test3.example.tld/keys/4"), Err(ActionError::NotFound)));
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use crate::fixtures::{create_community_fixture, create_thread_fixture, create_user_fixture, new_post_fixture};
+
+use chrono::{Duration, Utc};
+use diesel::Connection;
+
+use commune::db::establish_connection;
+use commune::db::actions::follow::{actor_count_followers, follow_actor_by_uri, get_follow, unfollow_actor_by_uri};
+use commune::db::actions::moderation::{
+    add_moderator, ban_actor, community_get_moderators, is_banned, is_moderator, remove_moderator,
+    set_post_pinned, set_post_removed, unban_actor,
+};
+use commune::db::actions::post::{community_count_threads, community_get_threads, insert_comment};
+use commune::errors::{ActionResult, ActionError};
+
+#[test]
+fn test_moderators() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let owner = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let user_actor = create_user_fixture(&conn, "misaka4e22", "test1.example.tld");
+    let other_actor = create_user_fixture(&conn, "misaka4e23", "test1.example.tld");
+    let community = create_community_fixture(&conn, "railgun", "test1.example.tld", &owner).actor;
+
+    // The owner moderates and follows the community.
+    assert!(is_moderator(&conn, &community, &owner.actor)?);
+    assert_eq!(community_get_moderators(&conn, &community)?, vec![owner.actor.clone()]);
+    assert_eq!(actor_count_followers(&conn, &community)?, 1);
+
+    // Only the owner appoints moderators.
+    assert!(matches!(
+        add_moderator(&conn, &other_actor.actor, &community, &user_actor.actor),
+        Err(ActionError::Forbidden)
+    ));
+    follow_actor_by_uri(&conn, user_actor.actor.uri.as_str(), community.uri.as_str(), None)?;
+    let moderator = add_moderator(&conn, &owner.actor, &community, &user_actor.actor)?;
+    assert_eq!(moderator.actor_id, user_actor.actor.id);
+    // Appointing again changes nothing.
+    assert_eq!(add_moderator(&conn, &owner.actor, &community, &user_actor.actor)?, moderator);
+    assert!(is_moderator(&conn, &community, &user_actor.actor)?);
+    assert!(!is_moderator(&conn, &community, &other_actor.actor)?);
+    assert_eq!(community_get_moderators(&conn, &community)?, vec![owner.actor.clone(), user_actor.actor.clone()]);
+    // Following again keeps the role.
+    follow_actor_by_uri(&conn, user_actor.actor.uri.as_str(), community.uri.as_str(), Some("https://test1.example.tld/activities/1"))?;
+    assert!(is_moderator(&conn, &community, &user_actor.actor)?);
+
+    assert_eq!(remove_moderator(&conn, &owner.actor, &community, &user_actor.actor)?, moderator);
+    assert!(!is_moderator(&conn, &community, &user_actor.actor)?);
+    assert!(matches!(
+        remove_moderator(&conn, &owner.actor, &community, &user_actor.actor),
+        Err(ActionError::NotFound)
+    ));
+    // Still following.
+    assert_eq!(actor_count_followers(&conn, &community)?, 2);
+    Ok(())
+}
+
+#[test]
+fn test_unfollow_keeps_roles() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let owner = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let user_actor = create_user_fixture(&conn, "misaka4e22", "test1.example.tld");
+    let community = create_community_fixture(&conn, "railgun", "test1.example.tld", &owner).actor;
+
+    add_moderator(&conn, &owner.actor, &community, &user_actor.actor)?;
+    follow_actor_by_uri(&conn, user_actor.actor.uri.as_str(), community.uri.as_str(), None)?;
+    assert_eq!(actor_count_followers(&conn, &community)?, 2);
+
+    // Unfollowing ends the subscription of the owner and of a moderator, but not their roles.
+    unfollow_actor_by_uri(&conn, owner.actor.uri.as_str(), community.uri.as_str())?;
+    unfollow_actor_by_uri(&conn, user_actor.actor.uri.as_str(), community.uri.as_str())?;
+    assert!(matches!(get_follow(&conn, &owner.actor, &community), Err(ActionError::NotFound)));
+    assert!(matches!(get_follow(&conn, &user_actor.actor, &community), Err(ActionError::NotFound)));
+    assert_eq!(actor_count_followers(&conn, &community)?, 0);
+    assert!(is_moderator(&conn, &community, &owner.actor)?);
+    assert!(is_moderator(&conn, &community, &user_actor.actor)?);
+    assert_eq!(community_get_moderators(&conn, &community)?, vec![owner.actor.clone(), user_actor.actor.clone()]);
+    Ok(())
+}
+
+#[test]
+fn test_remove_and_pin_posts() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let owner = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let user_actor = create_user_fixture(&conn, "misaka4e22", "test1.example.tld");
+    let community = create_community_fixture(&conn, "railgun", "test1.example.tld", &owner).actor;
+
+    let thread1 = create_thread_fixture(&conn, &user_actor.actor, &community, "https://test1.example.tld/posts/1", 10);
+    let thread2 = create_thread_fixture(&conn, &user_actor.actor, &community, "https://test1.example.tld/posts/2", 5);
+    let comment = insert_comment(&conn, &thread1, new_post_fixture(&user_actor.actor, &community, "https://test1.example.tld/comments/1", None, 1))?;
+
+    assert!(matches!(
+        set_post_pinned(&conn, &user_actor.actor, &thread1, true),
+        Err(ActionError::Forbidden)
+    ));
+    assert!(matches!(
+        set_post_pinned(&conn, &owner.actor, &comment, true),
+        Err(ActionError::InvalidForm)
+    ));
+    let thread1 = set_post_pinned(&conn, &owner.actor, &thread1, true)?;
+    assert!(thread1.is_pinned);
+    assert_eq!(community_get_threads(&conn, &community, 1)?, vec![thread1.clone(), thread2.clone()]);
+
+    assert!(matches!(
+        set_post_removed(&conn, &user_actor.actor, &thread2, true),
+        Err(ActionError::Forbidden)
+    ));
+    let thread2 = set_post_removed(&conn, &owner.actor, &thread2, true)?;
+    assert!(thread2.is_removed);
+    assert_eq!(community_get_threads(&conn, &community, 1)?, vec![thread1]);
+    assert_eq!(community_count_threads(&conn, &community)?, 1);
+
+    set_post_removed(&conn, &owner.actor, &thread2, false)?;
+    assert_eq!(community_count_threads(&conn, &community)?, 2);
+    Ok(())
+}
+
+#[test]
+fn test_ban_actor() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let owner = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let user_actor = create_user_fixture(&conn, "misaka4e22", "test1.example.tld");
+    let other_actor = create_user_fixture(&conn, "misaka4e23", "test1.example.tld");
+    let community = create_community_fixture(&conn, "railgun", "test1.example.tld", &owner).actor;
+
+    assert!(matches!(
+        ban_actor(&conn, &user_actor.actor, &community, &other_actor.actor, "", None),
+        Err(ActionError::Forbidden)
+    ));
+    // Moderators can't be banned.
+    assert!(matches!(
+        ban_actor(&conn, &owner.actor, &community, &owner.actor, "", None),
+        Err(ActionError::Forbidden)
+    ));
+
+    ban_actor(&conn, &owner.actor, &community, &user_actor.actor, "spam", None)?;
+    assert!(is_banned(&conn, &community, &user_actor.actor)?);
+    assert!(!is_banned(&conn, &community, &other_actor.actor)?);
+
+    // Expired bans don't count.
+    let expired = Utc::now().naive_utc() - Duration::minutes(1);
+    ban_actor(&conn, &owner.actor, &community, &other_actor.actor, "", Some(expired))?;
+    assert!(!is_banned(&conn, &community, &other_actor.actor)?);
+
+    let ban = unban_actor(&conn, &owner.actor, &community, &user_actor.actor)?;
+    assert_eq!(ban.reason, "spam");
+    assert!(!is_banned(&conn, &community, &user_actor.actor)?);
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use crate::fixtures::{create_group_fixture, create_thread_fixture, create_user_fixture, new_post_fixture};
+
+use chrono::Duration;
+use diesel::Connection;
+
+use commune::db::establish_connection;
+use commune::db::actions::post::{community_count_threads, community_get_threads, get_post_by_uri, get_post_view, insert_comment, insert_thread, post_get_replies, post_get_revisions, revise_post, thread_get_comments, tombstone_post};
+use commune::db::models::NewPost;
+use commune::errors::{ActionResult, ActionError};
+
+#[test]
+fn test_insert_thread() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let user_actor = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let community = create_group_fixture(&conn, "railgun", "test1.example.tld");
+
+    let thread1 = insert_thread(&conn, new_post_fixture(&user_actor.actor, &community, "https://test1.example.tld/posts/1", Some("First"), 10))?;
+    let thread2 = insert_thread(&conn, new_post_fixture(&user_actor.actor, &community, "https://test1.example.tld/posts/2", Some("Second"), 5))?;
+    assert!(thread1.is_thread());
+    assert_eq!(get_post_by_uri(&conn, "https://test1.example.tld/posts/1")?, thread1);
+
+    assert_eq!(community_get_threads(&conn, &community, 1)?, vec![thread2, thread1]);
+    assert_eq!(community_count_threads(&conn, &community)?, 2);
+    Ok(())
+}
+
+#[test]
+fn test_insert_comment() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let user_actor = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let community = create_group_fixture(&conn, "railgun", "test1.example.tld");
+    let other_community = create_group_fixture(&conn, "index", "test1.example.tld");
+
+    let thread = create_thread_fixture(&conn, &user_actor.actor, &community, "https://test1.example.tld/posts/1", 10);
+    // Comments belong to the community of their thread.
+    let comment = insert_comment(&conn, &thread, new_post_fixture(&user_actor.actor, &other_community, "https://test1.example.tld/comments/1", None, 5))?;
+    let reply = insert_comment(&conn, &comment, new_post_fixture(&user_actor.actor, &other_community, "https://test1.example.tld/comments/2", None, 1))?;
+    assert_eq!(comment.community_id, community.id);
+    assert_eq!(comment.thread_id, Some(thread.id));
+    assert_eq!(reply.in_reply_to_id, Some(comment.id));
+    assert_eq!(reply.thread_id, Some(thread.id));
+
+    assert_eq!(post_get_replies(&conn, &thread)?, vec![comment.clone()]);
+    assert_eq!(thread_get_comments(&conn, &thread)?, vec![comment, reply]);
+    assert_eq!(community_count_threads(&conn, &community)?, 1);
+    assert_eq!(community_count_threads(&conn, &other_community)?, 0);
+    Ok(())
+}
+
+#[test]
+fn test_tombstone_post() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let user_actor = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let community = create_group_fixture(&conn, "railgun", "test1.example.tld");
+
+    let thread = create_thread_fixture(&conn, &user_actor.actor, &community, "https://test1.example.tld/posts/1", 10);
+    let comment = insert_comment(&conn, &thread, new_post_fixture(&user_actor.actor, &community, "https://test1.example.tld/comments/1", None, 5))?;
+    let view = get_post_view(&conn, comment.clone())?;
+    assert_eq!(view.author, user_actor.actor);
+    assert_eq!(view.community, community);
+    assert_eq!(view.in_reply_to, Some(thread.clone()));
+
+    let deleted = tombstone_post(&conn, &thread)?;
+    assert!(deleted.is_deleted());
+    assert_eq!(deleted.title, None);
+    assert_eq!(deleted.content, "");
+    // Replies stay in the thread.
+    assert_eq!(thread_get_comments(&conn, &deleted)?, vec![comment]);
+    Ok(())
+}
+
+#[test]
+fn test_revise_post() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let user_actor = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let community = create_group_fixture(&conn, "railgun", "test1.example.tld");
+
+    let new_thread = new_post_fixture(&user_actor.actor, &community, "https://test1.example.tld/posts/1", Some("First"), 10);
+    let thread = insert_thread(&conn, new_thread.clone())?;
+    let updated_at = thread.published + Duration::minutes(5);
+    let revised = revise_post(&conn, &thread, &NewPost {
+        title: Some(String::from("First, edited")),
+        content: String::from("<p>Hello again</p>"),
+        updated_at: Some(updated_at),
+        ..new_thread
+    })?;
+    assert_eq!(revised.id, thread.id);
+    assert_eq!(revised.title.as_deref(), Some("First, edited"));
+    assert_eq!(revised.content, "<p>Hello again</p>");
+    assert_eq!(revised.updated_at, Some(updated_at));
+    assert_eq!(revised.published, thread.published);
+
+    let revisions = post_get_revisions(&conn, &revised)?;
+    assert_eq!(revisions.len(), 1);
+    assert_eq!(revisions[0].title.as_deref(), Some("First"));
+    assert_eq!(revisions[0].content, "<p>Hello</p>");
+    assert_eq!(revisions[0].published, thread.published);
+    Ok(())
+}
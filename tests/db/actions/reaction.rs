@@ -0,0 +1,94 @@
+use crate::fixtures::{create_community_fixture, create_group_fixture, create_thread_fixture, create_user_fixture};
+
+use diesel::Connection;
+
+use commune::db::establish_connection;
+use commune::db::actions::post::get_post_view;
+use commune::db::actions::reaction::{add_reaction, post_count_reactions, remove_reaction, remove_reaction_by_uri};
+use commune::db::models::{Post, ReactionCount};
+use commune::errors::{ActionResult, ActionError};
+
+#[test]
+fn test_reactions() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let author = create_user_fixture(&conn, "misaka4e21", "test1.example.tld").actor;
+    let reactor1 = create_user_fixture(&conn, "misaka4e22", "test2.example.tld").actor;
+    let reactor2 = create_user_fixture(&conn, "misaka4e23", "test2.example.tld").actor;
+    let reactor3 = create_user_fixture(&conn, "misaka4e24", "test3.example.tld").actor;
+    let community = create_group_fixture(&conn, "railgun", "test1.example.tld");
+    let thread = create_thread_fixture(&conn, &author, &community, "https://test1.example.tld/posts/1", 0);
+    let emoji_url = "https://test2.example.tld/emojis/blobcat.png";
+
+    add_reaction(&conn, &reactor1, &thread, "👍", None, Some("https://test2.example.tld/reacts/1"))?;
+    add_reaction(&conn, &reactor1, &thread, ":blobcat:", Some(emoji_url), Some("https://test2.example.tld/reacts/2"))?;
+    add_reaction(&conn, &reactor2, &thread, "👍", None, Some("https://test2.example.tld/reacts/3"))?;
+    // Reacting again with the same emoji doesn't count twice.
+    add_reaction(&conn, &reactor2, &thread, "👍", None, Some("https://test2.example.tld/reacts/4"))?;
+    assert!(matches!(add_reaction(&conn, &reactor2, &thread, " ", None, None), Err(ActionError::InvalidForm)));
+
+    // Another server has its own :blobcat:, which the same actor may react with too.
+    let other_emoji_url = "https://test3.example.tld/emojis/blobcat.png";
+    add_reaction(&conn, &reactor3, &thread, ":blobcat:", Some(other_emoji_url), Some("https://test3.example.tld/reacts/1"))?;
+    add_reaction(&conn, &reactor1, &thread, ":blobcat:", Some(other_emoji_url), Some("https://test2.example.tld/reacts/5"))?;
+    add_reaction(&conn, &reactor1, &thread, ":blobcat:", Some(other_emoji_url), Some("https://test2.example.tld/reacts/6"))?;
+
+    let counts = vec![
+        ReactionCount { content: String::from("👍"), emoji_url: None, count: 2 },
+        ReactionCount { content: String::from(":blobcat:"), emoji_url: Some(String::from(emoji_url)), count: 1 },
+        ReactionCount { content: String::from(":blobcat:"), emoji_url: Some(String::from(other_emoji_url)), count: 2 },
+    ];
+    assert_eq!(post_count_reactions(&conn, &thread)?, counts);
+    assert_eq!(get_post_view(&conn, thread.clone())?.reactions, counts);
+
+    // The reaction is only withdrawn by whoever sent it.
+    assert!(matches!(remove_reaction_by_uri(&conn, &reactor1, "https://test2.example.tld/reacts/4"), Err(ActionError::NotFound)));
+    assert!(matches!(remove_reaction_by_uri(&conn, &reactor2, "https://test2.example.tld/reacts/3"), Err(ActionError::NotFound)));
+    let reaction = remove_reaction_by_uri(&conn, &reactor2, "https://test2.example.tld/reacts/4")?;
+    assert_eq!(reaction.content, "👍");
+    assert!(matches!(remove_reaction(&conn, &reactor1, &thread, ":blobcat:", None), Err(ActionError::NotFound)));
+    let reaction = remove_reaction(&conn, &reactor1, &thread, ":blobcat:", Some(emoji_url))?;
+    assert_eq!(reaction.emoji_url.as_deref(), Some(emoji_url));
+    assert_eq!(post_count_reactions(&conn, &thread)?, vec![
+        ReactionCount { content: String::from("👍"), emoji_url: None, count: 1 },
+        ReactionCount { content: String::from(":blobcat:"), emoji_url: Some(String::from(other_emoji_url)), count: 2 },
+    ]);
+    Ok(())
+}
+
+#[test]
+fn test_apply_reaction() -> ActionResult<()> {
+    use commune::apub::actions::reactions::apply_reaction;
+    use commune::apub::models::Activity;
+    use commune::db::actions::moderation::set_post_removed;
+    use commune::db::actions::post::tombstone_post;
+    use std::collections::HashSet;
+
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let local_domains = ["test1.example.tld"].iter().map(|domain| String::from(*domain)).collect::<HashSet<String>>();
+    let owner = create_user_fixture(&conn, "misaka4e21", "test1.example.tld");
+    let reactor = create_user_fixture(&conn, "misaka4e22", "test2.example.tld").actor;
+    let community = create_community_fixture(&conn, "railgun", "test1.example.tld", &owner).actor;
+    let thread1 = create_thread_fixture(&conn, &owner.actor, &community, "https://test1.example.tld/posts/1", 10);
+    let thread2 = create_thread_fixture(&conn, &owner.actor, &community, "https://test1.example.tld/posts/2", 5);
+    let thread3 = create_thread_fixture(&conn, &owner.actor, &community, "https://test1.example.tld/posts/3", 0);
+    set_post_removed(&conn, &owner.actor, &thread2, true)?;
+    tombstone_post(&conn, &thread3)?;
+
+    let react = |post: &Post, id: &str| serde_json::from_value::<Activity>(serde_json::json!({
+        "type": "EmojiReact",
+        "id": id,
+        "actor": reactor.uri,
+        "object": post.uri,
+        "content": "👍",
+    })).unwrap();
+    let reaction = apply_reaction(&conn, &local_domains, &reactor, thread1.uri.as_str(), &react(&thread1, "https://test2.example.tld/reacts/1"))?;
+    assert_eq!(reaction.map(|reaction| reaction.post_id), Some(thread1.id));
+    // Deleted and removed posts take no reactions.
+    assert_eq!(apply_reaction(&conn, &local_domains, &reactor, thread2.uri.as_str(), &react(&thread2, "https://test2.example.tld/reacts/2"))?, None);
+    assert_eq!(apply_reaction(&conn, &local_domains, &reactor, thread3.uri.as_str(), &react(&thread3, "https://test2.example.tld/reacts/3"))?, None);
+    assert_eq!(post_count_reactions(&conn, &thread2)?, vec![]);
+    assert_eq!(post_count_reactions(&conn, &thread3)?, vec![]);
+    Ok(())
+}
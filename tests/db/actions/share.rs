@@ -0,0 +1,30 @@
+use crate::fixtures::{create_group_fixture, create_thread_fixture, create_user_fixture};
+
+use diesel::Connection;
+
+use commune::db::establish_connection;
+use commune::db::actions::post::get_post_by_id;
+use commune::db::actions::share::{add_share, remove_share};
+use commune::errors::{ActionResult, ActionError};
+
+#[test]
+fn test_shares() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let author = create_user_fixture(&conn, "misaka4e21", "test1.example.tld").actor;
+    let sharer = create_user_fixture(&conn, "misaka4e22", "test2.example.tld").actor;
+    let community = create_group_fixture(&conn, "railgun", "test1.example.tld");
+    let thread = create_thread_fixture(&conn, &author, &community, "https://test1.example.tld/posts/1", 0);
+
+    let share = add_share(&conn, &sharer, &thread, Some("https://test2.example.tld/announces/1"))?;
+    assert_eq!(share.uri.as_deref(), Some("https://test2.example.tld/announces/1"));
+    // Sharing again doesn't count twice.
+    let share = add_share(&conn, &sharer, &thread, Some("https://test2.example.tld/announces/2"))?;
+    assert_eq!(share.uri.as_deref(), Some("https://test2.example.tld/announces/1"));
+    assert_eq!(get_post_by_id(&conn, thread.id)?.shares, 1);
+
+    remove_share(&conn, &sharer, &thread)?;
+    assert_eq!(get_post_by_id(&conn, thread.id)?.shares, 0);
+    assert!(matches!(remove_share(&conn, &sharer, &thread), Err(ActionError::NotFound)));
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use crate::fixtures::{create_group_fixture, create_thread_fixture, create_user_fixture};
+
+use diesel::Connection;
+
+use commune::db::establish_connection;
+use commune::db::actions::post::get_post_by_id;
+use commune::db::actions::vote::{get_vote, remove_vote, set_vote};
+use commune::db::models::{VOTE_DOWN, VOTE_UP};
+use commune::errors::{ActionResult, ActionError};
+
+#[test]
+fn test_votes() -> ActionResult<()> {
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let author = create_user_fixture(&conn, "misaka4e21", "test1.example.tld").actor;
+    let voter1 = create_user_fixture(&conn, "misaka4e22", "test1.example.tld").actor;
+    let voter2 = create_user_fixture(&conn, "misaka4e23", "test2.example.tld").actor;
+    let community = create_group_fixture(&conn, "railgun", "test1.example.tld");
+    let thread = create_thread_fixture(&conn, &author, &community, "https://test1.example.tld/posts/1", 0);
+    assert_eq!(thread.score(), 0);
+
+    set_vote(&conn, &voter1, &thread, VOTE_UP, None)?;
+    let vote = set_vote(&conn, &voter2, &thread, VOTE_UP, Some("https://test2.example.tld/likes/1"))?;
+    assert_eq!(vote.activity_kind(), "Like");
+    let thread = get_post_by_id(&conn, thread.id)?;
+    assert_eq!((thread.upvotes, thread.downvotes), (2, 0));
+
+    // Voting again replaces the former vote.
+    set_vote(&conn, &voter2, &thread, VOTE_DOWN, Some("https://test2.example.tld/dislikes/1"))?;
+    let thread = get_post_by_id(&conn, thread.id)?;
+    assert_eq!((thread.upvotes, thread.downvotes), (1, 1));
+    assert_eq!(get_vote(&conn, &voter2, &thread)?.uri.as_deref(), Some("https://test2.example.tld/dislikes/1"));
+    assert!(matches!(set_vote(&conn, &voter2, &thread, 2, None), Err(ActionError::InvalidForm)));
+
+    let vote = remove_vote(&conn, &voter2, &thread)?;
+    assert_eq!(vote.score, VOTE_DOWN);
+    let thread = get_post_by_id(&conn, thread.id)?;
+    assert_eq!((thread.upvotes, thread.downvotes), (1, 0));
+    assert_eq!(thread.score(), 1);
+    assert!(matches!(get_vote(&conn, &voter2, &thread), Err(ActionError::NotFound)));
+    assert!(matches!(remove_vote(&conn, &voter2, &thread), Err(ActionError::NotFound)));
+    Ok(())
+}
+
+#[test]
+fn test_apply_undo_vote() -> ActionResult<()> {
+    use commune::apub::actions::votes::apply_undo_vote;
+    use commune::apub::models::Activity;
+    use std::collections::HashSet;
+
+    let conn = establish_connection();
+    conn.begin_test_transaction().map_err(|_e| ActionError::InternalError)?;
+    let local_domains = ["test1.example.tld"].iter().map(|domain| String::from(*domain)).collect::<HashSet<String>>();
+    let author = create_user_fixture(&conn, "misaka4e21", "test1.example.tld").actor;
+    let voter = create_user_fixture(&conn, "misaka4e22", "test2.example.tld").actor;
+    let community = create_group_fixture(&conn, "railgun", "test1.example.tld");
+    let thread = create_thread_fixture(&conn, &author, &community, "https://test1.example.tld/posts/1", 0);
+
+    let activity = |kind: &str, id: &str, object: serde_json::Value| serde_json::from_value::<Activity>(serde_json::json!({
+        "type": kind,
+        "id": id,
+        "actor": voter.uri,
+        "object": object,
+    })).unwrap();
+    let like1 = activity("Like", "https://test2.example.tld/likes/1", serde_json::json!(thread.uri));
+    let undo_like1 = activity("Undo", "https://test2.example.tld/undos/1", serde_json::to_value(&like1).unwrap());
+    let like2 = activity("Like", "https://test2.example.tld/likes/2", serde_json::json!(thread.uri));
+    let undo_like2 = activity("Undo", "https://test2.example.tld/undos/2", serde_json::to_value(&like2).unwrap());
+    set_vote(&conn, &voter, &thread, VOTE_UP, Some(like1.id.as_str()))?;
+    set_vote(&conn, &voter, &thread, VOTE_DOWN, Some("https://test2.example.tld/dislikes/1"))?;
+    set_vote(&conn, &voter, &thread, VOTE_UP, Some(like2.id.as_str()))?;
+
+    // A delayed Undo of the first Like doesn't withdraw the Like which came after it.
+    assert_eq!(apply_undo_vote(&conn, &local_domains, &voter, thread.uri.as_str(), VOTE_UP, &like1, &undo_like1)?, None);
+    assert_eq!(get_vote(&conn, &voter, &thread)?.uri.as_deref(), Some(like2.id.as_str()));
+    let vote = apply_undo_vote(&conn, &local_domains, &voter, thread.uri.as_str(), VOTE_UP, &like2, &undo_like2)?;
+    assert_eq!(vote.map(|vote| vote.score), Some(VOTE_UP));
+    assert!(matches!(get_vote(&conn, &voter, &thread), Err(ActionError::NotFound)));
+    let thread = get_post_by_id(&conn, thread.id)?;
+    assert_eq!((thread.upvotes, thread.downvotes), (0, 0));
+    Ok(())
+}
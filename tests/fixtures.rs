@@ -1,5 +1,6 @@
+use chrono::{Duration, Utc};
 use diesel::PgConnection;
-use commune::db::models::UserActor;
+use commune::db::models::{Actor, ActorType, CommunityActor, NewLocalActorBuilder, NewPost, Post, UserActor};
 use commune::db::actions;
 
 const COMMON_PASSWORD: &str = "123456";
@@ -14,4 +15,71 @@ pub fn create_user_fixture(
         Ok(useractor) => useractor,
         Err(e) => panic!("error: {}", e)
     }
-}
\ No newline at end of file
+}
+
+pub fn create_group_fixture(
+    conn: &PgConnection,
+    name: &str,
+    domain: &str
+) -> Actor {
+    let new_actor = NewLocalActorBuilder {
+        username: name,
+        domain,
+        lang: "und",
+        actor_type: ActorType::Group,
+        public_key_pem: "TEST_CERT",
+    }.build();
+    match actions::actor::insert_new_actor(conn, new_actor) {
+        Ok(actor) => actor,
+        Err(e) => panic!("error: {}", e)
+    }
+}
+
+pub fn create_community_fixture(
+    conn: &PgConnection,
+    name: &str,
+    domain: &str,
+    owner: &UserActor
+) -> CommunityActor {
+    match actions::community::create_community(conn, name, domain, name, "", "und", owner) {
+        Ok(community_actor) => community_actor,
+        Err(e) => panic!("error: {}", e)
+    }
+}
+
+/// A post by `author` in `community`, published `minutes_ago`: a Page if it has a title, or else
+/// a Note.
+pub fn new_post_fixture(
+    author: &Actor,
+    community: &Actor,
+    uri: &str,
+    title: Option<&str>,
+    minutes_ago: i64
+) -> NewPost {
+    NewPost {
+        uri: String::from(uri),
+        url: None,
+        kind: String::from(if title.is_some() { "Page" } else { "Note" }),
+        author_id: author.id,
+        community_id: community.id,
+        in_reply_to_id: None,
+        thread_id: None,
+        title: title.map(String::from),
+        content: String::from("<p>Hello</p>"),
+        published: Utc::now().naive_utc() - Duration::minutes(minutes_ago),
+        updated_at: None,
+    }
+}
+
+pub fn create_thread_fixture(
+    conn: &PgConnection,
+    author: &Actor,
+    community: &Actor,
+    uri: &str,
+    minutes_ago: i64
+) -> Post {
+    match actions::post::insert_thread(conn, new_post_fixture(author, community, uri, Some("First"), minutes_ago)) {
+        Ok(post) => post,
+        Err(e) => panic!("error: {}", e)
+    }
+}